@@ -1,5 +1,7 @@
 use clap::Parser;
-use stained::{agent, game};
+use serde::Serialize;
+use stained::{agent, env, eval, game};
+use std::io::Write;
 
 #[derive(Parser)]
 struct Args {
@@ -11,6 +13,46 @@ struct Args {
     repeats: usize,
     #[clap(long, value_parser, value_delimiter = ',', default_value = "0,1")]
     ai_levels: Vec<usize>,
+    /// Weights file for the heuristic agents (levels >= 1).
+    #[clap(long)]
+    weights: Option<String>,
+    /// Base seed for reproducible games; game i uses seed + i.
+    #[clap(long)]
+    seed: Option<u64>,
+    /// Write every decision as a JSON line to this file.
+    #[clap(long)]
+    dump: Option<String>,
+}
+
+#[derive(Serialize)]
+struct AgentInfo<'a> {
+    level: usize,
+    weights: Option<&'a eval::EvalWeights>,
+}
+
+#[derive(Serialize)]
+struct GameInfo<'a> {
+    index: usize,
+    seed: u64,
+    agents: Vec<AgentInfo<'a>>,
+}
+
+// One decision made during self-play, in the `env` encoding.
+#[derive(Serialize)]
+struct Decision {
+    turn: usize,
+    player: usize,
+    observation: Vec<f32>,
+    legal_actions: Vec<usize>,
+    action: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct DumpRecord<'a> {
+    game: &'a GameInfo<'a>,
+    #[serde(flatten)]
+    decision: Decision,
+    final_scores: &'a [game::ScoreBreakdown],
 }
 
 struct RunInfo {
@@ -19,8 +61,17 @@ struct RunInfo {
     winner_idx: usize,
 }
 
-fn run_game(args: &Args) -> Option<RunInfo> {
-    let mut g = match game::GameState::init(args.players) {
+fn run_game(
+    args: &Args,
+    weights: Option<&eval::EvalWeights>,
+    game_idx: usize,
+    mut dump: Option<&mut std::fs::File>,
+) -> Option<RunInfo> {
+    let seed = match args.seed {
+        Some(seed) => seed.wrapping_add(game_idx as u64),
+        None => rand::random(),
+    };
+    let mut g = match game::GameState::init_with_seed(args.players, seed) {
         Ok(game) => game,
         Err(e) => {
             eprintln!("Error creating game state: {e}");
@@ -28,20 +79,68 @@ fn run_game(args: &Args) -> Option<RunInfo> {
         }
     };
     let ais = (0..args.players)
-        .map(|i| agent::create_agent(args.ai_levels[i % args.ai_levels.len()]))
+        .map(
+            |i| match (args.ai_levels[i % args.ai_levels.len()], weights) {
+                (level @ 1.., Some(w)) => Box::new(
+                    agent::HeuristicAgent::new(w.clone()).with_depth(level),
+                ),
+                (level, _) => agent::create_agent(level),
+            },
+        )
         .collect::<Vec<_>>();
+    let mut decisions = Vec::new();
     loop {
         if !args.quiet {
             println!("P{}: {:?}", g.curr_player_idx, g.phase);
             g.current_player().pretty_print();
         }
-        let act = ais[g.curr_player_idx].choose_action(&g);
+        // Agents play on a copy that can't foresee rolls or bag draws.
+        let mut view = g.clone();
+        view.hide_future();
+        let act = ais[g.curr_player_idx].choose_action(&view);
         if !args.quiet {
             println!(" {act:?}");
         }
+        if dump.is_some() {
+            decisions.push(Decision {
+                turn: decisions.len(),
+                player: g.curr_player_idx,
+                observation: env::observe(&g),
+                legal_actions: env::legal_action_indices(&g),
+                action: env::encode_action(&g, &act),
+            });
+        }
         match g.take_turn(&act) {
             Ok(true) => {
                 let scores = g.player_scores();
+                if let Some(file) = dump.as_mut() {
+                    let info = GameInfo {
+                        index: game_idx,
+                        seed,
+                        agents: (0..args.players)
+                            .map(|i| {
+                                let level =
+                                    args.ai_levels[i % args.ai_levels.len()];
+                                AgentInfo {
+                                    level,
+                                    weights: weights.filter(|_| level > 0),
+                                }
+                            })
+                            .collect(),
+                    };
+                    for decision in decisions {
+                        let record = DumpRecord {
+                            game: &info,
+                            decision,
+                            final_scores: &scores,
+                        };
+                        let line = serde_json::to_string(&record).ok()?;
+                        if let Err(e) = writeln!(file, "{line}") {
+                            eprintln!("Error writing dump: {e}");
+                            return None;
+                        }
+                    }
+                }
                 let winner_idx = g.winner_idx()?;
                 if !args.quiet {
                     println!("Game over: winner={winner_idx}",);
@@ -108,13 +207,31 @@ impl Stats {
 
 fn main() {
     let args = Args::parse();
+    let weights = match args.weights.as_deref().map(eval::EvalWeights::load) {
+        Some(Ok(w)) => Some(w),
+        Some(Err(e)) => {
+            eprintln!("Error loading weights: {e}");
+            return;
+        }
+        None => None,
+    };
     let mut time_stats = Stats::new();
     let mut score_stats = Stats::new();
     let mut unfilled_stats = Stats::new();
     let mut win_counts = vec![0; args.players];
-    for _ in 0..args.repeats {
+    let mut dump = match args.dump.as_deref().map(std::fs::File::create) {
+        Some(Ok(file)) => Some(file),
+        Some(Err(e)) => {
+            eprintln!("Error creating dump file: {e}");
+            return;
+        }
+        None => None,
+    };
+    for game_idx in 0..args.repeats {
         let start_time = std::time::Instant::now();
-        if let Some(info) = run_game(&args) {
+        if let Some(info) =
+            run_game(&args, weights.as_ref(), game_idx, dump.as_mut())
+        {
             score_stats.add(info.winner_score);
             unfilled_stats.add(info.winner_unfilled);
             win_counts[info.winner_idx] += 1;
@@ -0,0 +1,131 @@
+use clap::Parser;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use stained::agent::{Agent, HeuristicAgent};
+use stained::eval::EvalWeights;
+use stained::game::GameState;
+
+/// Hill-climbing search over heuristic agent weights using self-play.
+#[derive(Parser)]
+struct Args {
+    /// Number of players in each game.
+    #[clap(short, long, default_value_t = 2)]
+    players: usize,
+    /// Seed for the search and the games it plays.
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+    /// Number of candidate weights to try.
+    #[clap(long, default_value_t = 50)]
+    iterations: usize,
+    /// Number of game seeds per comparison; each seed is played once per seat.
+    #[clap(long, default_value_t = 10)]
+    games: usize,
+    /// Maximum size of a random perturbation to a single weight.
+    #[clap(long, default_value_t = 0.5)]
+    step: f64,
+    /// Starting weights (defaults to EvalWeights::default()).
+    #[clap(long)]
+    init: Option<String>,
+    /// File to write the best weights to.
+    #[clap(short, long, default_value = "weights.json")]
+    output: String,
+}
+
+// Plays one game with `challenger` in seat `seat` and `incumbent` elsewhere,
+// returning the challenger's score minus the best incumbent score.
+fn play_game(
+    players: usize,
+    seed: u64,
+    seat: usize,
+    challenger: &HeuristicAgent,
+    incumbent: &HeuristicAgent,
+) -> Option<i32> {
+    let mut g = GameState::init_with_seed(players, seed).ok()?;
+    loop {
+        let ai: &dyn Agent = if g.curr_player_idx == seat {
+            challenger
+        } else {
+            incumbent
+        };
+        let mut view = g.clone();
+        view.hide_future();
+        if g.take_turn(&ai.choose_action(&view)).ok()? {
+            break;
+        }
+    }
+    let scores: Vec<i32> =
+        g.player_scores().iter().map(|s| s.total()).collect();
+    let best_other = scores
+        .iter()
+        .enumerate()
+        .filter_map(|(i, s)| if i == seat { None } else { Some(*s) })
+        .max()?;
+    Some(scores[seat] - best_other)
+}
+
+// Total score margin of `candidate` over `current` across all test games.
+fn compare(
+    args: &Args,
+    round_seed: u64,
+    candidate: &EvalWeights,
+    current: &EvalWeights,
+) -> i32 {
+    let challenger = HeuristicAgent::new(candidate.clone());
+    let incumbent = HeuristicAgent::new(current.clone());
+    let mut margin = 0;
+    for game in 0..args.games as u64 {
+        for seat in 0..args.players {
+            let seed = round_seed.wrapping_add(game);
+            margin +=
+                play_game(args.players, seed, seat, &challenger, &incumbent)
+                    .unwrap_or(0);
+        }
+    }
+    margin
+}
+
+fn perturb(weights: &EvalWeights, step: f64, rng: &mut StdRng) -> EvalWeights {
+    let mut next = weights.clone();
+    let w = match rng.random_range(0..5) {
+        0 => &mut next.objectives,
+        1 => &mut next.secret_color,
+        2 => &mut next.unused_tokens,
+        3 => &mut next.empty_slots,
+        _ => &mut next.unfillable,
+    };
+    *w += rng.random_range(-step..=step);
+    next
+}
+
+fn main() {
+    let args = Args::parse();
+    let mut weights = match &args.init {
+        Some(path) => match EvalWeights::load(path) {
+            Ok(w) => w,
+            Err(e) => {
+                eprintln!("Error loading {path}: {e}");
+                return;
+            }
+        },
+        None => EvalWeights::default(),
+    };
+    let mut rng = StdRng::seed_from_u64(args.seed);
+    for iter in 0..args.iterations {
+        let candidate = perturb(&weights, args.step, &mut rng);
+        let round_seed = rng.random();
+        let margin = compare(&args, round_seed, &candidate, &weights);
+        if margin > 0 {
+            weights = candidate;
+        }
+        println!(
+            "Iteration {iter}: margin={margin}, weights={}",
+            serde_json::to_string(&weights).unwrap()
+        );
+    }
+    let json = serde_json::to_string_pretty(&weights).unwrap();
+    if let Err(e) = std::fs::write(&args.output, json) {
+        eprintln!("Error writing {}: {e}", args.output);
+    } else {
+        println!("Wrote weights to {}", args.output);
+    }
+}
@@ -1,16 +1,43 @@
-use crate::color::Dice;
+use crate::color::{Dice, PoolDie};
 use crate::constants::{BOARD_COLS, BOARD_ROWS};
+use crate::eval::{EvalWeights, evaluate};
 use crate::game::{GameState, Player};
 use crate::tool::{ToolData, ToolType};
 use crate::turn::{ActionType, TurnAction, TurnPhase};
 use rand::seq::{IndexedRandom, IteratorRandom};
+use std::time::Instant;
 
 pub trait Agent {
     fn choose_action(&self, game: &GameState) -> TurnAction;
+    // Like `choose_action`, but returns by `deadline` with the best action
+    // found so far. Agents that can't stop early ignore the deadline.
+    fn choose_action_by(
+        &self,
+        game: &GameState,
+        _deadline: Instant,
+    ) -> TurnAction {
+        self.choose_action(game)
+    }
+    // Up to `k` actions this agent would consider, best first.
+    fn suggest_actions(&self, game: &GameState, k: usize) -> Vec<TurnAction> {
+        let mut actions = vec![self.choose_action(game)];
+        actions.truncate(k);
+        actions
+    }
+    // Weights the agent scores positions with, if it uses any.
+    fn weights(&self) -> Option<&EvalWeights> {
+        None
+    }
 }
 
-pub fn create_agent(_difficulty: usize) -> Box<dyn Agent + Send> {
-    Box::<RandomAgent>::default()
+// 0 plays randomly, 1 greedily drafts the best die, and 2 or more also
+// weighs each tool by the best draft it allows.
+pub fn create_agent(difficulty: usize) -> Box<dyn Agent + Send> {
+    match difficulty {
+        0 => Box::<RandomAgent>::default(),
+        1 => Box::<HeuristicAgent>::default(),
+        _ => Box::new(HeuristicAgent::default().with_depth(2)),
+    }
 }
 
 #[derive(Default)]
@@ -24,11 +51,7 @@ impl Agent for RandomAgent {
                 let idx = ActionType::SelectTemplate(
                     (0..me.templates.len()).choose(&mut rng).unwrap(),
                 );
-                TurnAction {
-                    idx,
-                    coords: None,
-                    tool: None,
-                }
+                TurnAction::new(idx, None)
             }
             TurnPhase::FirstDraft | TurnPhase::SecondDraft => {
                 if let Some(action) =
@@ -36,29 +59,240 @@ impl Agent for RandomAgent {
                 {
                     action.clone()
                 } else if let Some(action) =
-                    all_valid_tools(game, me).choose(&mut rng)
+                    all_valid_tools(game).choose(&mut rng)
                 {
                     action.clone()
                 } else {
-                    TurnAction::pass()
+                    pass_actions(game)
+                        .choose(&mut rng)
+                        .cloned()
+                        .unwrap_or_else(TurnAction::pass)
                 }
             }
             TurnPhase::GameOver => TurnAction::pass(),
         }
     }
+    fn suggest_actions(&self, game: &GameState, k: usize) -> Vec<TurnAction> {
+        legal_actions(game)
+            .choose_multiple(&mut rand::rng(), k)
+            .cloned()
+            .collect()
+    }
+}
+
+// Greedy agent that picks the action with the best board evaluation.
+pub struct HeuristicAgent {
+    weights: EvalWeights,
+    // Number of actions in a row to search. Tools don't end the turn, so
+    // they're only considered with a depth of 2 or more.
+    depth: usize,
+}
+impl Default for HeuristicAgent {
+    fn default() -> Self {
+        Self::new(EvalWeights::default())
+    }
+}
+impl HeuristicAgent {
+    pub fn new(weights: EvalWeights) -> Self {
+        Self { weights, depth: 1 }
+    }
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth.max(1);
+        self
+    }
+    // Candidate actions with their evaluations, best first. Ties keep the
+    // order the candidates were generated in. Past the deadline, only the
+    // candidates evaluated so far are ranked.
+    fn ranked_actions(
+        &self,
+        game: &GameState,
+        deadline: Option<Instant>,
+    ) -> Vec<(f64, TurnAction)> {
+        self.rank(game, self.depth, deadline)
+    }
+    fn rank(
+        &self,
+        game: &GameState,
+        depth: usize,
+        deadline: Option<Instant>,
+    ) -> Vec<(f64, TurnAction)> {
+        let me = game.current_player();
+        let candidates = match game.phase {
+            TurnPhase::SelectTemplate => (0..me.templates.len())
+                .map(|idx| {
+                    TurnAction::new(ActionType::SelectTemplate(idx), None)
+                })
+                .collect(),
+            TurnPhase::FirstDraft | TurnPhase::SecondDraft => {
+                let mut actions = all_valid_drafts(game, me);
+                if depth > 1 {
+                    actions.extend(all_valid_tools(game));
+                }
+                actions.extend(pass_actions(game));
+                actions
+            }
+            TurnPhase::GameOver => vec![],
+        };
+        let mut ranked = Vec::new();
+        for action in candidates {
+            if !ranked.is_empty()
+                && deadline.is_some_and(|d| Instant::now() >= d)
+            {
+                break;
+            }
+            let mut next = game.clone();
+            if next.take_turn(&action).is_err() {
+                continue;
+            }
+            // A tool is worth the best action it leads to.
+            let followup = match action.idx {
+                ActionType::UseTool(_) => {
+                    self.rank(&next, depth - 1, deadline).first().map(|r| r.0)
+                }
+                _ => None,
+            };
+            let score = followup.unwrap_or_else(|| {
+                evaluate(&next, game.curr_player_idx, &self.weights)
+            });
+            ranked.push((score, action));
+        }
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        ranked
+    }
+}
+impl Agent for HeuristicAgent {
+    fn choose_action(&self, game: &GameState) -> TurnAction {
+        self.ranked_actions(game, None)
+            .into_iter()
+            .next()
+            .map_or_else(TurnAction::pass, |(_, action)| action)
+    }
+    fn choose_action_by(
+        &self,
+        game: &GameState,
+        deadline: Instant,
+    ) -> TurnAction {
+        self.ranked_actions(game, Some(deadline))
+            .into_iter()
+            .next()
+            .map_or_else(TurnAction::pass, |(_, action)| action)
+    }
+    fn suggest_actions(&self, game: &GameState, k: usize) -> Vec<TurnAction> {
+        self.ranked_actions(game, None)
+            .into_iter()
+            .take(k)
+            .map(|(_, action)| action)
+            .collect()
+    }
+    fn weights(&self) -> Option<&EvalWeights> {
+        Some(&self.weights)
+    }
+}
+
+// Every action the current player could legally take.
+pub fn legal_actions(game: &GameState) -> Vec<TurnAction> {
+    let me = game.current_player();
+    match game.phase {
+        TurnPhase::SelectTemplate => (0..me.templates.len())
+            .map(|idx| TurnAction::new(ActionType::SelectTemplate(idx), None))
+            .collect(),
+        TurnPhase::FirstDraft | TurnPhase::SecondDraft => {
+            let mut actions = all_valid_drafts(game, me);
+            actions.extend(all_valid_tools(game));
+            actions.extend(pass_actions(game));
+            actions
+                .into_iter()
+                .map(|action| with_die_id(game, action))
+                .collect()
+        }
+        TurnPhase::GameOver => vec![],
+    }
+}
+
+// Names the dice an action uses by id as well as by index, so clients can
+// still refer to them after the pool or round track changes.
+fn with_die_id(game: &GameState, action: TurnAction) -> TurnAction {
+    let draft_idx = match (&action.idx, &action.tool) {
+        (ActionType::DraftDie(idx, face), _)
+            if action.coords.is_some() || face.is_some() =>
+        {
+            Some(*idx)
+        }
+        (ActionType::UseTool(_), Some(data)) => data.draft_idx(),
+        _ => None,
+    };
+    let pool_id = |idx: Option<usize>| game.draft_pool.get(idx?)?.id();
+    let tool = action.tool.map(|data| match data {
+        ToolData::SwapDraftedDieWithRoundTrack {
+            draft_idx,
+            round_idx: (round, i),
+            ..
+        } => ToolData::SwapDraftedDieWithRoundTrack {
+            draft_idx,
+            round_idx: (round, i),
+            round_die_id: game
+                .round_track
+                .get(round)
+                .and_then(|dice| dice.get(i)?.id()),
+        },
+        data => data,
+    });
+    TurnAction {
+        die_id: pool_id(draft_idx),
+        payment_id: pool_id(action.payment),
+        tool,
+        ..action
+    }
+}
+
+// Random legal action, used when an agent fails to produce a valid one.
+// It's drawn from the game's RNG, so seeded games stay reproducible.
+pub fn fallback_action(game: &GameState) -> TurnAction {
+    legal_actions(game)
+        .choose(&mut game.fork_rng())
+        .cloned()
+        .unwrap_or_else(TurnAction::pass)
+}
+
+// Ways to end the turn without placing a die. A pending wildcard can't be
+// passed on; it's returned to the pool with a face that can't be placed.
+pub fn pass_actions(game: &GameState) -> Vec<TurnAction> {
+    let Some(idx) = game.pending_wild() else {
+        return vec![TurnAction::pass()];
+    };
+    let wild = game.draft_pool[idx];
+    (1..=6)
+        .filter(|&face| {
+            wild.with_face(Some(face))
+                .is_ok_and(|die| !game.current_player().can_place_anywhere(die))
+        })
+        .map(|face| {
+            TurnAction::new(ActionType::DraftDie(idx, Some(face)), None)
+        })
+        .collect()
 }
 
 fn draftable_dice(game: &GameState) -> Vec<(ActionType, Dice)> {
     let mut draftable = Vec::new();
+    let wild = game.pending_wild();
     for (idx, die) in game.draft_pool.iter().enumerate() {
-        if die.face == 0 {
-            for face in 1..=6 {
-                let mut wild = *die;
-                wild.face = face;
-                draftable.push((ActionType::DraftDie(idx, Some(face)), wild));
+        if wild.is_some_and(|w| w != idx) {
+            continue;
+        }
+        match *die {
+            PoolDie::Rolled(die) => {
+                draftable.push((ActionType::DraftDie(idx, None), die));
+            }
+            PoolDie::Wild(..) => {
+                for face in 1..=6 {
+                    if let Ok(rolled) = die.with_face(Some(face)) {
+                        draftable.push((
+                            ActionType::DraftDie(idx, Some(face)),
+                            rolled,
+                        ));
+                    }
+                }
             }
-        } else {
-            draftable.push((ActionType::DraftDie(idx, None), *die));
         }
     }
     draftable
@@ -70,11 +304,8 @@ fn all_valid_drafts(game: &GameState, player: &Player) -> Vec<TurnAction> {
         for row in 0..BOARD_ROWS {
             for col in 0..BOARD_COLS {
                 if player.can_place_die((row, col), die).is_ok() {
-                    valid_drafts.push(TurnAction {
-                        idx: idx.clone(),
-                        coords: Some((row, col)),
-                        tool: None,
-                    });
+                    valid_drafts
+                        .push(TurnAction::new(idx.clone(), Some((row, col))));
                 }
             }
         }
@@ -82,97 +313,99 @@ fn all_valid_drafts(game: &GameState, player: &Player) -> Vec<TurnAction> {
     valid_drafts
 }
 
-fn all_valid_tools(game: &GameState, player: &Player) -> Vec<TurnAction> {
+fn all_valid_tools(game: &GameState) -> Vec<TurnAction> {
     let usable_tools = game
         .tools
         .iter()
         .enumerate()
-        .filter(|(_, tool)| player.can_use_tool(tool).is_ok());
+        .filter(|(idx, _)| game.can_use_tool(*idx).is_ok());
     usable_tools
         .flat_map(|(idx, tool)| {
             let mut options = Vec::new();
             match tool.tool_type {
                 ToolType::FlipDraftedDie => {
-                    for draft_idx in 0..game.draft_pool.len() {
-                        options.push(TurnAction {
-                            idx: ActionType::UseTool(idx),
-                            coords: None,
-                            tool: Some(ToolData::FlipDraftedDie { draft_idx }),
-                        });
+                    for (draft_idx, die) in game.draft_pool.iter().enumerate() {
+                        if die.is_wild() {
+                            continue;
+                        }
+                        options.push(TurnAction::use_tool(
+                            idx,
+                            ToolData::FlipDraftedDie { draft_idx },
+                        ));
                     }
                 }
                 ToolType::RerollDraftedDie => {
                     for draft_idx in 0..game.draft_pool.len() {
-                        options.push(TurnAction {
-                            idx: ActionType::UseTool(idx),
-                            coords: None,
-                            tool: Some(ToolData::RerollDraftedDie { draft_idx }),
-                        });
+                        options.push(TurnAction::use_tool(
+                            idx,
+                            ToolData::RerollDraftedDie { draft_idx },
+                        ));
                     }
                 }
                 ToolType::BumpDraftedDie => {
                     for (draft_idx, die) in game.draft_pool.iter().enumerate() {
+                        let Some(die) = die.rolled() else {
+                            continue;
+                        };
                         if die.face < 6 {
-                            options.push(TurnAction {
-                                idx: ActionType::UseTool(idx),
-                                coords: None,
-                                tool: Some(ToolData::BumpDraftedDie {
+                            options.push(TurnAction::use_tool(
+                                idx,
+                                ToolData::BumpDraftedDie {
                                     draft_idx,
                                     is_increment: true,
-                                }),
-                            });
+                                },
+                            ));
                         }
                         if die.face > 1 {
-                            options.push(TurnAction {
-                                idx: ActionType::UseTool(idx),
-                                coords: None,
-                                tool: Some(ToolData::BumpDraftedDie {
+                            options.push(TurnAction::use_tool(
+                                idx,
+                                ToolData::BumpDraftedDie {
                                     draft_idx,
                                     is_increment: false,
-                                }),
-                            });
+                                },
+                            ));
                         }
                     }
                 }
                 ToolType::RerollAllDiceInPool => {
-                    options.push(TurnAction {
-                        idx: ActionType::UseTool(idx),
-                        coords: None,
-                        tool: Some(ToolData::RerollAllDiceInPool),
-                    });
+                    options.push(TurnAction::use_tool(
+                        idx,
+                        ToolData::RerollAllDiceInPool,
+                    ));
                 }
                 ToolType::PlaceIgnoringAdjacency => {
-                    options.push(TurnAction {
-                        idx: ActionType::UseTool(idx),
-                        coords: None,
-                        tool: Some(ToolData::PlaceIgnoringAdjacency),
-                    });
+                    options.push(TurnAction::use_tool(
+                        idx,
+                        ToolData::PlaceIgnoringAdjacency,
+                    ));
                 }
                 ToolType::SwapDraftedDieWithRoundTrack => {
                     for draft_idx in 0..game.draft_pool.len() {
-                        for (i, round_dice) in game.round_track.iter().enumerate() {
+                        for (i, round_dice) in
+                            game.round_track.iter().enumerate()
+                        {
                             for j in 0..round_dice.len() {
-                                options.push(TurnAction {
-                                    idx: ActionType::UseTool(idx),
-                                    coords: None,
-                                    tool: Some(ToolData::SwapDraftedDieWithRoundTrack {
+                                options.push(TurnAction::use_tool(
+                                    idx,
+                                    ToolData::SwapDraftedDieWithRoundTrack {
                                         draft_idx,
                                         round_idx: (i, j),
-                                    }),
-                                });
+                                        round_die_id: None,
+                                    },
+                                ));
                             }
                         }
                     }
                 }
                 ToolType::SwapDraftedDieWithBag => {
+                    if game.dice_in_bag() == 0 {
+                        return options;
+                    }
                     for draft_idx in 0..game.draft_pool.len() {
-                        options.push(TurnAction {
-                            idx: ActionType::UseTool(idx),
-                            coords: None,
-                            tool: Some(ToolData::SwapDraftedDieWithBag {
-                                draft_idx,
-                            }),
-                        });
+                        options.push(TurnAction::use_tool(
+                            idx,
+                            ToolData::SwapDraftedDieWithBag { draft_idx },
+                        ));
                     }
                 }
                 ToolType::MoveDieIgnoringColor => {
@@ -193,6 +426,26 @@ fn all_valid_tools(game: &GameState, player: &Player) -> Vec<TurnAction> {
             }
             options
         })
+        .flat_map(|action| with_payments(game, action))
+        .collect()
+}
+
+// Solo games need a die to pay for each tool, so each tool action is
+// repeated once per die that could pay for it.
+fn with_payments(game: &GameState, action: TurnAction) -> Vec<TurnAction> {
+    if !game.is_solo() {
+        return vec![action];
+    }
+    let (ActionType::UseTool(idx), Some(data)) = (&action.idx, &action.tool)
+    else {
+        return vec![action];
+    };
+    game.payment_options(*idx, data)
+        .into_iter()
+        .map(|payment| TurnAction {
+            payment: Some(payment),
+            ..action.clone()
+        })
         .collect()
 }
 
@@ -200,6 +453,41 @@ fn all_valid_tools(game: &GameState, player: &Player) -> Vec<TurnAction> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_agent_levels() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::board::parse_board;
+        use crate::objective::Objective;
+        use crate::tool::Tool;
+        let board = parse_board(
+            "
+            R1 Y2 G3 B4 __
+            __ __ __ __ __
+            __ __ __ __ __
+            __ __ __ __ __
+            ",
+        )?;
+        // Flipping the Y2 to a 5 completes the row's numbers.
+        let game = GameState::builder(2)
+            .board(0, board)
+            .tokens(0, 2)
+            .draft_pool(vec!["Y2".parse()?])
+            .round_track(vec![vec!["P1".parse()?], vec!["G1".parse()?]])
+            .tools(vec![Tool {
+                tool_type: ToolType::FlipDraftedDie,
+                cost: 1,
+                tokens: 0,
+                used: false,
+            }])
+            .objectives(vec![Objective::RowNumbers(5)])
+            .phase(TurnPhase::FirstDraft)
+            .build()?;
+        let greedy = create_agent(1).choose_action(&game);
+        assert!(matches!(greedy.idx, ActionType::DraftDie(..)), "{greedy:?}");
+        let deeper = create_agent(2).choose_action(&game);
+        assert!(matches!(deeper.idx, ActionType::UseTool(0)), "{deeper:?}");
+        Ok(())
+    }
+
     #[test]
     fn test_random_agent() -> Result<(), Box<dyn std::error::Error>> {
         let mut game = GameState::init(2)?;
@@ -218,4 +506,179 @@ mod tests {
         assert_eq!(final_scores.len(), 2, "{:?}", final_scores);
         Ok(())
     }
+
+    #[test]
+    fn test_heuristic_agent() -> Result<(), Box<dyn std::error::Error>> {
+        let mut game = GameState::init(2)?;
+        let agent = create_agent(1);
+        while !game.take_turn(&agent.choose_action(&game))? {}
+        // The greedy agent should fill most of its board.
+        for score in game.player_scores() {
+            assert!(score.empty_slots > -10, "{:?}", score);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_quick_game() -> Result<(), Box<dyn std::error::Error>> {
+        let config = crate::config::GameConfig::from_json(
+            r#"{"rules": {"num_rounds": 5, "num_tools": 4, "num_objectives": 5}}"#,
+        )?;
+        let mut game = GameState::init_with_config(4, &config)?;
+        assert_eq!(game.tools.len(), 4);
+        let agent = create_agent(1);
+        while !game.take_turn(&agent.choose_action(&game))? {}
+        assert_eq!(game.round_track.len(), 5);
+        for score in game.player_scores() {
+            assert_eq!(score.objectives.len(), 5);
+            // Only ten dice can be placed in five rounds.
+            assert!(score.empty_slots <= -10, "{score:?}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_seeded_self_play() -> Result<(), Box<dyn std::error::Error>> {
+        let agent = create_agent(1);
+        let mut totals = Vec::new();
+        for _ in 0..2 {
+            let mut game = GameState::init_with_seed(3, 42)?;
+            while !game.take_turn(&agent.choose_action(&game))? {}
+            let scores = game.player_scores();
+            totals.push(scores.iter().map(|s| s.total()).collect::<Vec<_>>());
+        }
+        assert_eq!(totals[0], totals[1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_large_tables() -> Result<(), Box<dyn std::error::Error>> {
+        let agent = create_agent(1);
+        for num_players in [5, 6] {
+            let mut game = GameState::init_with_seed(num_players, 7)?;
+            while game.phase == TurnPhase::SelectTemplate {
+                game.take_turn(&agent.choose_action(&game))?;
+            }
+            // Half the pool is rolled for each pass around the table.
+            assert_eq!(game.draft_pool.len(), num_players + 1);
+            let start = game.curr_player_idx;
+            let mut order = Vec::new();
+            while game.round_track.is_empty() {
+                let turn = (game.phase, game.curr_player_idx);
+                if order.last() != Some(&turn) {
+                    order.push(turn);
+                }
+                game.take_turn(&agent.choose_action(&game))?;
+            }
+            let seats =
+                |pass: usize| pass * num_players..(pass + 1) * num_players;
+            assert!(
+                order[seats(0)].iter().all(|t| t.0 == TurnPhase::FirstDraft)
+            );
+            assert!(
+                order[seats(1)]
+                    .iter()
+                    .all(|t| t.0 == TurnPhase::SecondDraft)
+            );
+            let players: Vec<usize> = order.iter().map(|t| t.1).collect();
+            let mut expected: Vec<usize> = (0..num_players)
+                .map(|i| (start + i) % num_players)
+                .collect();
+            expected.extend(expected.clone().into_iter().rev());
+            assert_eq!(players, expected);
+            while !game.take_turn(&agent.choose_action(&game))? {}
+            assert_eq!(game.player_scores().len(), num_players);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_solo_game() -> Result<(), Box<dyn std::error::Error>> {
+        let agent = create_agent(1);
+        let mut tools_used = 0;
+        for seed in 0..10 {
+            let mut game = GameState::init_with_seed(1, seed)?;
+            assert_eq!(game.objectives.len(), 2);
+            game.take_turn(&agent.choose_action(&game))?;
+            assert_eq!(game.current_player().tokens, 0);
+            assert_eq!(game.draft_pool.len(), 4);
+            // Tools are paid for with a die of the tool's color.
+            if let Some(action) = legal_actions(&game)
+                .into_iter()
+                .find(|a| matches!(a.idx, ActionType::UseTool(_)))
+            {
+                let ActionType::UseTool(idx) = action.idx else {
+                    unreachable!()
+                };
+                let payment = action.payment.ok_or("missing payment")?;
+                let color = game.draft_pool[payment].color();
+                assert_eq!(color, game.tools[idx].tool_type.color());
+                let mut unpaid = action.clone();
+                unpaid.payment = None;
+                unpaid.payment_id = None;
+                assert!(game.clone().take_turn(&unpaid).is_err());
+                // The payment die can be named by id alone.
+                let mut by_id = action.clone();
+                by_id.payment = Some(99);
+                game.take_turn(&by_id)?;
+                assert_eq!(game.draft_pool.len(), 3);
+                assert!(game.tools[idx].used);
+                tools_used += 1;
+            }
+            while !game.take_turn(&agent.choose_action(&game))? {}
+            let score = &game.player_scores()[0];
+            assert_eq!(score.target, Some(game.solo_target()));
+            assert_eq!(score.unused_tokens, 0);
+            assert_eq!(
+                game.winner_idx().is_some(),
+                score.total() > game.solo_target()
+            );
+        }
+        assert!(tools_used > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deadline() -> Result<(), Box<dyn std::error::Error>> {
+        let mut game = GameState::init_with_seed(2, 1)?;
+        let agent = create_agent(2);
+        while !game.is_finished() {
+            // Even a deadline that has already passed yields a legal action.
+            let action = agent.choose_action_by(&game, Instant::now());
+            game.take_turn(&action)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_fallback_is_reproducible() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let play = |seed| -> Result<String, Box<dyn std::error::Error>> {
+            let mut game = GameState::init_with_seed(3, seed)?;
+            while !game.take_turn(&fallback_action(&game))? {}
+            Ok(serde_json::to_string(&game)?)
+        };
+        assert_eq!(play(7)?, play(7)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_random_self_play_invariants()
+    -> Result<(), Box<dyn std::error::Error>> {
+        // Random legal actions, including tools, for every table size.
+        for num_players in 1..=crate::constants::MAX_PLAYERS {
+            for seed in 0..5 {
+                let mut game = GameState::init_with_seed(num_players, seed)?;
+                game.validate()?;
+                while !game.take_turn(&fallback_action(&game))? {
+                    game.validate()?;
+                }
+                // Deserializing refills the bag and validates the game.
+                let json = serde_json::to_string(&game)?;
+                let restored: GameState = serde_json::from_str(&json)?;
+                assert_eq!(restored.dice_in_bag(), game.dice_in_bag());
+            }
+        }
+        Ok(())
+    }
 }
@@ -1,17 +1,135 @@
 use blau_api::{DynSafeGameAPI, GameAPI, PlayerInfo, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 use crate::{
-    agent::{Agent, create_agent},
+    agent::{
+        Agent, create_agent, fallback_action, legal_actions, pass_actions,
+    },
+    color::PoolDie,
+    config::GameConfig,
+    event::GameEvent,
     game::{GameState, ScoreBreakdown},
-    turn::TurnAction,
+    hint::hints,
+    turn::{ActionType, TurnAction},
 };
 
-/// View of the current game for a specific player.
+/// View of the current game for a specific player, or for spectators.
 #[derive(Serialize)]
 struct PlayerView<'a> {
     game: GameState, // Redacted to avoid leaking secrets
     winner_id: Option<&'a str>,
+    config: &'a GameConfig,
+    // Players the game is waiting on. Usually just the current player, but
+    // everyone who hasn't picked during simultaneous template selection.
+    to_act: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    legal_actions: Option<LegalActions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_action: Option<LastAction<'a>>,
+    // Time left for each player, if the game is timed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    time_left: Vec<TimeLeft>,
+}
+
+#[derive(Serialize)]
+struct TimeLeft {
+    // Only for players the game is waiting on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    turn_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    game_ms: Option<u64>,
+}
+
+/// The viewer's legal actions, grouped the way a client offers them.
+#[derive(Serialize, Default)]
+struct LegalActions {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    templates: Vec<TurnAction>,
+    // Every placement of each draftable pool die.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    dice: Vec<DieActions>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<ToolActions>,
+    // Ways to end the turn without placing a die.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pass: Vec<TurnAction>,
+}
+
+#[derive(Serialize)]
+struct DieActions {
+    die: PoolDie,
+    actions: Vec<TurnAction>,
+}
+
+#[derive(Serialize)]
+struct ToolActions {
+    tool: usize,
+    actions: Vec<TurnAction>,
+}
+
+impl LegalActions {
+    fn new(game: &GameState) -> Self {
+        let mut legal = Self::default();
+        for action in legal_actions(game) {
+            match action.idx {
+                ActionType::SelectTemplate(_) => legal.templates.push(action),
+                ActionType::DraftDie(idx, _) if action.coords.is_some() => {
+                    let die = game.draft_pool[idx];
+                    match legal.dice.iter_mut().find(|d| d.die.id() == die.id())
+                    {
+                        Some(group) => group.actions.push(action),
+                        None => legal.dice.push(DieActions {
+                            die,
+                            actions: vec![action],
+                        }),
+                    }
+                }
+                ActionType::DraftDie(..) => legal.pass.push(action),
+                ActionType::UseTool(tool) => {
+                    match legal.tools.iter_mut().find(|t| t.tool == tool) {
+                        Some(group) => group.actions.push(action),
+                        None => legal.tools.push(ToolActions {
+                            tool,
+                            actions: vec![action],
+                        }),
+                    }
+                }
+            }
+        }
+        legal
+    }
+}
+
+/// The previous action, so clients can show what happened without
+/// comparing views.
+#[derive(Serialize)]
+struct LastAction<'a> {
+    player_id: &'a str,
+    // Left out for other players' hidden template picks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action: Option<TurnAction>,
+    // E.g. "placed Y4 at (2, 3)".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+/// Sent to players and spectators after every action. Clients apply the
+/// events to their copy of the game, and only need a full view to resync.
+#[derive(Serialize)]
+struct Notice<'a> {
+    events: Vec<GameEvent>,
+    to_act: Vec<&'a str>,
+}
+
+/// An action as sent by a client. The player only needs to be given when
+/// acting out of turn, i.e. during simultaneous template selection.
+#[derive(Deserialize)]
+struct PlayerAction {
+    #[serde(default)]
+    player_id: Option<String>,
+    #[serde(flatten)]
+    action: TurnAction,
 }
 
 /// Final data to store for viewing completed games.
@@ -19,6 +137,22 @@ struct PlayerView<'a> {
 struct FinalState {
     game: GameState,
     scores: Vec<ScoreBreakdown>,
+    #[serde(default)]
+    agent_failures: Vec<usize>,
+    #[serde(default)]
+    config: GameConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    seat_changes: Vec<SeatChange>,
+}
+
+/// A seat changing hands during the game, e.g. when a player leaves.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct SeatChange {
+    player: usize,
+    // Number of rounds finished at the time.
+    round: usize,
+    // The AI level now playing the seat, or None for a human.
+    ai_level: Option<u8>,
 }
 
 pub struct StainedAPI {
@@ -28,45 +162,354 @@ pub struct StainedAPI {
     player_ids: Vec<String>,
     // None if human player
     agents: Vec<Option<Box<dyn Agent + Send>>>,
+    // Level of each player's AI, or None if human
+    ai_levels: Vec<Option<u8>>,
     // Indicates if the game is over
     game_over: bool,
+    // Number of invalid actions produced by each AI player
+    agent_failures: Vec<usize>,
+    // Options used to set up the game
+    config: GameConfig,
+    // IDs to send spectator views to
+    spectator_ids: Vec<String>,
+    // Spectator views and notices after each of the last few actions, oldest
+    // first. The oldest is what spectators currently see.
+    spectator_views: VecDeque<(String, String)>,
+    // The most recent action and the player who took it.
+    last_action: Option<(usize, TurnAction)>,
+    // Every time a seat was handed to an AI or back to a human.
+    seat_changes: Vec<SeatChange>,
+    // Current time in milliseconds, for turn timers.
+    clock: Box<dyn Fn() -> u64 + Send>,
+    // Time each player has spent on their turns so far, in milliseconds.
+    time_used: Vec<u64>,
+    // When the game started waiting on each player, if it is.
+    waiting_since: Vec<Option<u64>>,
+}
+
+fn system_clock() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
 }
 
 impl StainedAPI {
-    fn view(&self, player_idx: usize) -> Result<String> {
+    // Number of times each player's AI produced an invalid action that had
+    // to be replaced. Always zero for human players.
+    pub fn agent_failures(&self) -> &[usize] {
+        &self.agent_failures
+    }
+    // Adds a spectator, who is notified like a human player but sees no
+    // secrets, and lags `spectator_delay` actions behind the game. Views
+    // are only kept while there are spectators, so the first one to join
+    // starts from the current position.
+    pub fn add_spectator(&mut self, spectator_id: String) -> Result<()> {
+        if self.spectator_ids.is_empty() {
+            self.spectator_views.clear();
+            self.spectator_views
+                .push_back((self.view(None)?, String::new()));
+        }
+        self.spectator_ids.push(spectator_id);
+        Ok(())
+    }
+    // Hands a player's seat to an AI of the given level, e.g. when they
+    // leave, or back to a human if None. An AI moves right away if it's
+    // the seat's turn. Does nothing if the seat already has that level.
+    pub fn set_player_ai<F: FnMut(&str, &str)>(
+        &mut self,
+        player_id: &str,
+        ai_level: Option<u8>,
+        mut notice_cb: F,
+    ) -> Result<()> {
+        if self.game_over {
+            return Err("Game is over".into());
+        }
+        let idx = self.player_idx(player_id)?;
+        if self.ai_levels[idx] == ai_level {
+            return Ok(());
+        }
+        self.ai_levels[idx] = ai_level;
+        self.agents[idx] = ai_level.map(|lvl| create_agent(1 + lvl as usize));
+        self.seat_changes.push(SeatChange {
+            player: idx,
+            round: self.state.round_track.len(),
+            ai_level,
+        });
+        // A returning human starts their turn afresh.
+        if let Some(since) = self.waiting_since[idx].take() {
+            self.time_used[idx] += (self.clock)().saturating_sub(since);
+        }
+        self.update_clocks();
+        let event = GameEvent::SeatChanged {
+            player: idx,
+            ai_level,
+        };
+        self.broadcast(&[event], &mut notice_cb)?;
+        self.process_agents(&mut notice_cb)
+    }
+    // Up to `k` suggested actions for a player the game is waiting on, best
+    // first, as chosen by an AI of the given level.
+    pub fn hints(
+        &self,
+        player_id: &str,
+        level: u8,
+        k: usize,
+    ) -> Result<String> {
+        let idx = self.player_idx(player_id)?;
+        if !self.waiting_on().any(|i| i == idx) {
+            return Err("Not your turn".into());
+        }
+        // Hints only use what the player can see, and can't foresee rolls
+        // or draws from the bag.
         let mut game = self.state.clone();
+        game.redact_secrets(idx);
+        game.hide_future();
+        game.curr_player_idx = idx;
+        let agent = create_agent(1 + level as usize);
+        Ok(serde_json::to_string(&hints(&game, agent.as_ref(), k))?)
+    }
+    // Replaces the clock used for turn timers, which is the system time by
+    // default.
+    pub fn set_clock(&mut self, clock: impl Fn() -> u64 + Send + 'static) {
+        self.clock = Box::new(clock);
+    }
+    // Moves for every human player who has run out of time. Call this
+    // regularly in timed games; it also happens before every action.
+    pub fn check_clocks<F: FnMut(&str, &str)>(
+        &mut self,
+        mut notice_cb: F,
+    ) -> Result<()> {
+        self.play_timeouts(&mut notice_cb)?;
+        Ok(())
+    }
+    pub fn spectator_view(&self) -> Result<String> {
+        match self.spectator_views.front() {
+            Some((view, _)) => Ok(view.clone()),
+            None => self.view(None),
+        }
+    }
+    // Game as seen by the given player, or by spectators if None.
+    fn view(&self, viewer: Option<usize>) -> Result<String> {
+        let mut game = self.state.clone();
+        let mut config = self.config.clone();
         let winner_id = if self.game_over {
-            let scores = game.player_scores();
-            let max_score = scores.iter().map(|s| s.total()).max().unwrap();
-            let max_indices: Vec<usize> = scores
-                .iter()
-                .enumerate()
-                .filter_map(|(idx, score)| {
-                    if score.total() == max_score {
-                        Some(idx)
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-            // TODO: Handle ties properly
-            Some(self.player_ids[max_indices[0]].as_str())
+            // Solo games have no winner if the target score wasn't beaten.
+            game.winner_idx().map(|idx| self.player_ids[idx].as_str())
         } else {
-            game.redact_secrets(player_idx);
+            match viewer {
+                Some(idx) => game.redact_secrets(idx),
+                None => {
+                    game.hide_secrets();
+                    // The seed would give away the order of the bag.
+                    config.seed = None;
+                }
+            }
             None
         };
-        Ok(serde_json::to_string(&PlayerView { game, winner_id })?)
+        // Only the players the game is waiting on have legal actions.
+        let legal_actions = match viewer {
+            Some(idx)
+                if self.config.show_legal_actions
+                    && self.waiting_on().any(|i| i == idx) =>
+            {
+                let mut state = self.state.clone();
+                state.curr_player_idx = idx;
+                Some(LegalActions::new(&state))
+            }
+            _ => None,
+        };
+        let view = PlayerView {
+            game,
+            winner_id,
+            config: &config,
+            to_act: self
+                .waiting_on()
+                .map(|idx| self.player_ids[idx].as_str())
+                .collect(),
+            legal_actions,
+            last_action: self.last_action(viewer),
+            time_left: self.time_left(),
+        };
+        Ok(serde_json::to_string(&view)?)
+    }
+    // The last action as seen by the given player, or by spectators if None.
+    fn last_action(&self, viewer: Option<usize>) -> Option<LastAction<'_>> {
+        let (actor, action) = self.last_action.as_ref()?;
+        let events: Vec<GameEvent> = self
+            .state
+            .events()
+            .iter()
+            .map(|event| event.redacted(viewer))
+            .collect();
+        let hidden =
+            self.state.selecting_simultaneously() && viewer != Some(*actor);
+        Some(LastAction {
+            player_id: self.player_ids[*actor].as_str(),
+            action: (!hidden).then(|| action.clone()),
+            description: events.iter().find_map(GameEvent::describe),
+        })
+    }
+    // Events as seen by the given player, or by spectators if None.
+    fn notice(
+        &self,
+        events: &[GameEvent],
+        viewer: Option<usize>,
+    ) -> Result<String> {
+        let notice = Notice {
+            events: events.iter().map(|event| event.redacted(viewer)).collect(),
+            to_act: self
+                .waiting_on()
+                .map(|idx| self.player_ids[idx].as_str())
+                .collect(),
+        };
+        Ok(serde_json::to_string(&notice)?)
+    }
+    fn waiting_on(&self) -> impl Iterator<Item = usize> + '_ {
+        let state = &self.state;
+        (0..self.player_ids.len()).filter(move |&idx| {
+            !self.game_over
+                && if state.selecting_simultaneously() {
+                    !state.players[idx].templates.is_empty()
+                } else {
+                    idx == state.curr_player_idx
+                }
+        })
+    }
+    fn time_left(&self) -> Vec<TimeLeft> {
+        let config = &self.config;
+        if config.turn_time_ms.is_none() && config.game_time_ms.is_none() {
+            return Vec::new();
+        }
+        let now = (self.clock)();
+        (0..self.player_ids.len())
+            .map(|idx| {
+                let turn = self.waiting_since[idx]
+                    .map_or(0, |since| now.saturating_sub(since));
+                let waiting = self.waiting_since[idx].is_some();
+                TimeLeft {
+                    turn_ms: config
+                        .turn_time_ms
+                        .filter(|_| waiting)
+                        .map(|limit| limit.saturating_sub(turn)),
+                    game_ms: config.game_time_ms.map(|limit| {
+                        limit.saturating_sub(self.time_used[idx] + turn)
+                    }),
+                }
+            })
+            .collect()
+    }
+    // Starts the clocks of players the game has started waiting on, and
+    // stops the others.
+    fn update_clocks(&mut self) {
+        let now = (self.clock)();
+        let waiting: Vec<usize> = self.waiting_on().collect();
+        for idx in 0..self.player_ids.len() {
+            match self.waiting_since[idx] {
+                None if waiting.contains(&idx) => {
+                    self.waiting_since[idx] = Some(now);
+                }
+                Some(since) if !waiting.contains(&idx) => {
+                    self.time_used[idx] += now.saturating_sub(since);
+                    self.waiting_since[idx] = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    // Moves for human players who have run out of time, and returns who
+    // they were.
+    fn play_timeouts<F: FnMut(&str, &str)>(
+        &mut self,
+        mut notice_cb: F,
+    ) -> Result<Vec<usize>> {
+        let mut timed_out = Vec::new();
+        loop {
+            let time_left = self.time_left();
+            let expired = self.waiting_on().find(|&idx| {
+                self.agents[idx].is_none()
+                    && time_left.get(idx).is_some_and(|left| {
+                        left.turn_ms == Some(0) || left.game_ms == Some(0)
+                    })
+            });
+            let Some(idx) = expired else {
+                return Ok(timed_out);
+            };
+            let action = self.timeout_action(idx);
+            self.do_action(idx, &action, &mut notice_cb)?;
+            self.process_agents(&mut notice_cb)?;
+            timed_out.push(idx);
+        }
+    }
+    // The move made for a player who ran out of time.
+    fn timeout_action(&self, idx: usize) -> TurnAction {
+        let mut game = self.state.clone();
+        game.curr_player_idx = idx;
+        let action = match self.config.timeout_ai_level {
+            Some(level) => {
+                let mut view = game.clone();
+                view.hide_future();
+                create_agent(1 + level as usize).choose_action(&view)
+            }
+            None => pass_actions(&game)
+                .into_iter()
+                .next()
+                .unwrap_or_else(TurnAction::pass),
+        };
+        match game.clone().take_turn(&action) {
+            Ok(_) => action,
+            Err(_) => fallback_action(&game),
+        }
+    }
+    fn player_idx(&self, player_id: &str) -> Result<usize> {
+        Ok(self
+            .player_ids
+            .iter()
+            .position(|id| id == player_id)
+            .ok_or("Unknown player ID")?)
     }
     fn do_action<F: FnMut(&str, &str)>(
         &mut self,
+        idx: usize,
         action: &TurnAction,
-        mut notice_cb: F,
+        notice_cb: F,
     ) -> Result<()> {
         // Take the action.
-        self.game_over = self.state.take_turn(action)?;
-        // Notify all human players of the action.
+        self.game_over = self.state.take_turn_as(idx, action)?;
+        self.last_action = Some((idx, action.clone()));
+        // The turn clock keeps running for as long as the same player is
+        // waited on, e.g. after they use a tool.
+        self.update_clocks();
+        let events = self.state.events().to_vec();
+        self.broadcast(&events, notice_cb)
+    }
+    // Sends events to human players and, after `spectator_delay` actions,
+    // to spectators.
+    fn broadcast<F: FnMut(&str, &str)>(
+        &mut self,
+        events: &[GameEvent],
+        mut notice_cb: F,
+    ) -> Result<()> {
         for idx in self.human_player_idxs() {
-            notice_cb(self.player_ids[idx].as_str(), self.view(idx)?.as_str());
+            notice_cb(
+                self.player_ids[idx].as_str(),
+                self.notice(events, Some(idx))?.as_str(),
+            );
+        }
+        // Spectators are sent the events from `spectator_delay` actions ago,
+        // and catch up once the game is over.
+        if self.spectator_ids.is_empty() {
+            return Ok(());
+        }
+        self.spectator_views
+            .push_back((self.view(None)?, self.notice(events, None)?));
+        let delay = self.config.spectator_delay.unwrap_or(0);
+        while self.spectator_views.len() > delay + 1
+            || (self.game_over && self.spectator_views.len() > 1)
+        {
+            self.spectator_views.pop_front();
+            for id in &self.spectator_ids {
+                notice_cb(id, &self.spectator_views[0].1);
+            }
         }
         Ok(())
     }
@@ -79,38 +522,105 @@ impl StainedAPI {
         &mut self,
         mut notice_cb: F,
     ) -> Result<()> {
+        // AI players don't wait their turn to pick a template when everyone
+        // picks at once.
+        while self.state.selecting_simultaneously() {
+            let ai = self.waiting_on().find(|&idx| self.agents[idx].is_some());
+            let Some(idx) = ai else {
+                break;
+            };
+            let action = self.agent_action(idx);
+            self.do_action(idx, &action, &mut notice_cb)?;
+        }
         while !self.game_over
-            && let Some(ai) = &self.agents[self.state.curr_player_idx]
+            && self.agents[self.state.curr_player_idx].is_some()
         {
-            let action = ai.choose_action(&self.state);
-            self.do_action(&action, &mut notice_cb)?;
+            let idx = self.state.curr_player_idx;
+            let action = self.agent_action(idx);
+            self.do_action(idx, &action, &mut notice_cb)?;
         }
         Ok(())
     }
+    // Asks an AI player for its action, replacing it with a random legal one
+    // if it's invalid.
+    fn agent_action(&mut self, idx: usize) -> TurnAction {
+        // Agents always act as the current player, and can't foresee rolls
+        // or draws from the bag.
+        let mut game = self.state.clone();
+        game.curr_player_idx = idx;
+        let Some(ai) = &self.agents[idx] else {
+            return fallback_action(&game);
+        };
+        let mut view = game.clone();
+        view.hide_future();
+        let action = match self.config.ai_time_budget_ms {
+            Some(budget) => ai.choose_action_by(
+                &view,
+                std::time::Instant::now()
+                    + std::time::Duration::from_millis(budget),
+            ),
+            None => ai.choose_action(&view),
+        };
+        // Check the action on a copy so that a bad AI can't stall the game.
+        if game.clone().take_turn(&action).is_ok() {
+            action
+        } else {
+            self.agent_failures[idx] += 1;
+            fallback_action(&game)
+        }
+    }
 }
 impl GameAPI for StainedAPI {
-    fn init(players: &[PlayerInfo], _params: Option<&str>) -> Result<Self> {
-        let state = GameState::init(players.len())?;
+    fn init(players: &[PlayerInfo], params: Option<&str>) -> Result<Self> {
+        let config = match params {
+            Some(json) if !json.trim().is_empty() => {
+                GameConfig::from_json(json)?
+            }
+            _ => GameConfig::default(),
+        };
+        let state = GameState::init_with_config(players.len(), &config)?;
         let player_ids = players.iter().map(|p| p.id.clone()).collect();
         let agents = players
             .iter()
             .map(|p| p.level.map(|lvl| create_agent(1 + lvl as usize)))
             .collect();
+        let ai_levels = players.iter().map(|p| p.level).collect();
         Ok(Self {
             state,
             player_ids,
             agents,
+            ai_levels,
             game_over: false,
+            agent_failures: vec![0; players.len()],
+            config,
+            spectator_ids: Vec::new(),
+            spectator_views: VecDeque::new(),
+            last_action: None,
+            seat_changes: Vec::new(),
+            clock: Box::new(system_clock),
+            time_used: vec![0; players.len()],
+            waiting_since: vec![None; players.len()],
         })
     }
 
     fn restore(player_info: &[PlayerInfo], final_state: &str) -> Result<Self> {
+        // Deserializing the game also validates it.
         let fs: FinalState = serde_json::from_str(final_state)?;
         Ok(Self {
             state: fs.game,
             player_ids: player_info.iter().map(|p| p.id.clone()).collect(),
             agents: vec![],
+            ai_levels: player_info.iter().map(|p| p.level).collect(),
             game_over: true,
+            agent_failures: fs.agent_failures,
+            config: fs.config,
+            spectator_ids: Vec::new(),
+            spectator_views: VecDeque::new(),
+            last_action: None,
+            seat_changes: fs.seat_changes,
+            clock: Box::new(system_clock),
+            time_used: vec![0; player_info.len()],
+            waiting_since: vec![None; player_info.len()],
         })
     }
 
@@ -123,6 +633,10 @@ impl GameAPI for StainedAPI {
         for idx in self.human_player_idxs() {
             notice_cb(self.player_ids[idx].as_str(), &msg);
         }
+        for id in &self.spectator_ids {
+            notice_cb(id, &msg);
+        }
+        self.update_clocks();
         // Advance to wait for the next player action.
         self.process_agents(notice_cb)?;
         Ok(())
@@ -136,8 +650,19 @@ impl GameAPI for StainedAPI {
         if self.game_over {
             return Err("Game is over".into());
         }
-        let action: TurnAction = serde_json::from_str(action)?;
-        self.do_action(&action, &mut notice_cb)?;
+        let PlayerAction { player_id, action } = serde_json::from_str(action)?;
+        let idx = match player_id {
+            Some(id) => self.player_idx(&id)?,
+            None => self.state.curr_player_idx,
+        };
+        // An action that arrives too late was already made for the player.
+        if self.play_timeouts(&mut notice_cb)?.contains(&idx) {
+            return Err("Out of time".into());
+        }
+        if self.game_over {
+            return Err("Game is over".into());
+        }
+        self.do_action(idx, &action, &mut notice_cb)?;
         // Advance to wait for the next player action.
         self.process_agents(&mut notice_cb)?;
         Ok(())
@@ -156,17 +681,15 @@ impl DynSafeGameAPI for StainedAPI {
         let fs = FinalState {
             game: self.state.clone(),
             scores: self.state.player_scores(),
+            agent_failures: self.agent_failures.clone(),
+            config: self.config.clone(),
+            seat_changes: self.seat_changes.clone(),
         };
         Ok(serde_json::to_string(&fs)?)
     }
 
     fn player_view(&self, player_id: &str) -> Result<String> {
-        let player_idx = self
-            .player_ids
-            .iter()
-            .position(|id| id == player_id)
-            .ok_or("Unknown player ID")?;
-        self.view(player_idx)
+        self.view(Some(self.player_idx(player_id)?))
     }
 
     fn current_player_id(&self) -> &str {
@@ -207,6 +730,11 @@ fn exercise_api() {
     .unwrap();
     // Should have notified both players of the action.
     assert_eq!(num_notices, 2);
+    // The chosen window is visible to both players.
+    let view: serde_json::Value =
+        serde_json::from_str(&game.player_view("bar").unwrap()).unwrap();
+    let players = view["game"]["players"].as_array().unwrap();
+    assert!(players.iter().any(|p| p["template"]["name"].is_string()));
 }
 
 #[test]
@@ -219,6 +747,8 @@ fn self_play() {
     // Run until game over
     game.start(1234, |_, _| {}).unwrap();
     assert!(game.is_game_over());
+    // Nobody was watching, so no spectator views were kept.
+    assert!(game.spectator_views.is_empty());
     // Smoke test the final_state method.
     let final_state = game.final_state().unwrap();
     assert!(final_state.starts_with("{"), "{final_state}");
@@ -231,3 +761,574 @@ fn self_play() {
     };
     assert!(restored_game.is_game_over());
 }
+
+#[test]
+fn restore_baseline_final_state() {
+    // Saved before dice had ids, tools held tokens and boards recorded
+    // their template. Unplaced wildcards went to the round track as `X0`.
+    let final_state = r#"{"game":{"players":[{"tokens":1,"board":[[{"slot":4,"die":"B4"},{"slot":null,"die":"R5"},{"slot":2,"die":"P2"},{"slot":5,"die":"R5"},{"slot":"G"}],[{"slot":null,"die":"P1"},{"slot":null,"die":"B2"},{"slot":6,"die":"R6"},{"slot":"G","die":"G3"},{"slot":2,"die":"R2"}],[{"slot":null,"die":"R4"},{"slot":3},{"slot":"G","die":"G5"},{"slot":4,"die":"P4"},{"slot":null,"die":"Y5"}],[{"slot":5,"die":"P5"},{"slot":"G","die":"G3"},{"slot":1,"die":"Y1"},{"slot":null,"die":"R2"},{"slot":null,"die":"P4"}]],"secret":"G","active_tool":null},{"tokens":0,"board":[[{"slot":null,"die":"G5"},{"slot":4},{"slot":null,"die":"Y4"},{"slot":"Y"},{"slot":6,"die":"P6"}],[{"slot":"R"},{"slot":null,"die":"R2"},{"slot":2},{"slot":null,"die":"B6"},{"slot":null,"die":"G5"}],[{"slot":null,"die":"P1"},{"slot":null,"die":"G6"},{"slot":"R","die":"R1"},{"slot":"P"},{"slot":1,"die":"B1"}],[{"slot":"B","die":"B5"},{"slot":"Y","die":"Y4"},{"slot":null,"die":"G3"},{"slot":null,"die":"P1"},{"slot":null,"die":"Y6"}]],"secret":"B","active_tool":null}],"start_player_idx":1,"curr_player_idx":1,"phase":"GameOver","draft_pool":[],"round_track":[["Y4"],["P4"],["B2"],["G5"],["Y1"],["Y4"],["P5","Y2"],["R4"],["P6","B3","R0","B3"],["G2","Y4","B5","P0"]],"tools":[{"tool_type":"BumpDraftedDie","cost":2},{"tool_type":"SwapDraftedDieWithBag","cost":2},{"tool_type":"FlipDraftedDie","cost":2}],"objectives":[{"Colors":4},{"ColumnColors":5},{"RowColors":6}]},"scores":[{"objectives":[8,5,0],"secret_color":11,"unused_tokens":1,"empty_slots":-2},{"objectives":[8,5,0],"secret_color":12,"unused_tokens":0,"empty_slots":-5}]}"#;
+    let players = vec![
+        PlayerInfo::ai("bot1".into(), 1),
+        PlayerInfo::ai("bot2".into(), 1),
+    ];
+    let game = match StainedAPI::restore(&players, final_state) {
+        Ok(g) => g,
+        Err(e) => panic!("Failed to restore game: {e}"),
+    };
+    assert!(game.is_game_over());
+    let faces: Vec<u8> = game
+        .state
+        .round_track
+        .iter()
+        .flatten()
+        .map(|d| d.face)
+        .collect();
+    assert!(faces.iter().all(|f| (1..=6).contains(f)), "{faces:?}");
+    assert_eq!(game.state.tools[0].tokens, 1);
+}
+
+#[test]
+fn invalid_agent_actions() {
+    struct BadAgent;
+    impl Agent for BadAgent {
+        fn choose_action(&self, _game: &GameState) -> TurnAction {
+            TurnAction::new(crate::turn::ActionType::SelectTemplate(99), None)
+        }
+    }
+    let players = vec![
+        PlayerInfo::ai("good".into(), 1),
+        PlayerInfo::ai("bad".into(), 1),
+    ];
+    let mut game: StainedAPI = GameAPI::init(&players, None).unwrap();
+    game.agents[1] = Some(Box::new(BadAgent));
+    // The bad agent's actions are replaced, so the game still finishes.
+    game.start(1234, |_, _| {}).unwrap();
+    assert!(game.is_game_over());
+    let failures = game.agent_failures();
+    assert_eq!(failures[0], 0);
+    assert!(failures[1] > 0, "{failures:?}");
+    // Failure counts are kept in the final state.
+    let restored =
+        StainedAPI::restore(&players, &game.final_state().unwrap()).unwrap();
+    assert_eq!(restored.agent_failures(), failures);
+}
+
+#[test]
+fn ai_deadlines() {
+    use std::time::{Duration, Instant};
+    // Expects the time budget as a deadline.
+    struct PatientAgent;
+    impl Agent for PatientAgent {
+        fn choose_action(&self, _game: &GameState) -> TurnAction {
+            panic!("no deadline given");
+        }
+        fn choose_action_by(
+            &self,
+            game: &GameState,
+            deadline: Instant,
+        ) -> TurnAction {
+            assert!(deadline <= Instant::now() + Duration::from_millis(20));
+            fallback_action(game)
+        }
+    }
+    // Ignores the deadline.
+    struct SlowAgent;
+    impl Agent for SlowAgent {
+        fn choose_action(&self, game: &GameState) -> TurnAction {
+            std::thread::sleep(Duration::from_millis(30));
+            fallback_action(game)
+        }
+    }
+    let players = vec![
+        PlayerInfo::ai("patient".into(), 1),
+        PlayerInfo::ai("slow".into(), 1),
+    ];
+    let params = r#"{"seed": 1, "ai_time_budget_ms": 20}"#;
+    let mut game: StainedAPI = GameAPI::init(&players, Some(params)).unwrap();
+    game.agents = vec![Some(Box::new(PatientAgent)), Some(Box::new(SlowAgent))];
+    game.start(1, |_, _| {}).unwrap();
+    assert!(game.is_game_over());
+    // Slow moves are still played; only invalid ones count as failures.
+    assert_eq!(game.agent_failures(), [0, 0]);
+}
+
+#[test]
+fn agents_cant_foresee_rerolls() {
+    use crate::tool::{Tool, ToolData, ToolType};
+    use std::sync::{Arc, Mutex};
+    // Rerolls the first pool die on its copy of the game, then asks for the
+    // same reroll in the real one.
+    struct Reroller(Arc<Mutex<Option<u8>>>);
+    impl Agent for Reroller {
+        fn choose_action(&self, game: &GameState) -> TurnAction {
+            let action = TurnAction::use_tool(
+                0,
+                ToolData::RerollDraftedDie { draft_idx: 0 },
+            );
+            let mut next = game.clone();
+            next.take_turn(&action).unwrap();
+            *self.0.lock().unwrap() =
+                next.draft_pool[0].rolled().map(|d| d.face);
+            action
+        }
+    }
+    let players = vec![
+        PlayerInfo::ai("foo".into(), 1),
+        PlayerInfo::ai("bar".into(), 1),
+    ];
+    let mut foreseen = 0;
+    for seed in 0..20 {
+        let mut game: StainedAPI = GameAPI::init(&players, None).unwrap();
+        game.state = GameState::builder(2)
+            .seed(seed)
+            .tokens(0, 1)
+            .draft_pool(vec!["R1".parse().unwrap()])
+            .tools(vec![Tool {
+                tool_type: ToolType::RerollDraftedDie,
+                cost: 1,
+                tokens: 0,
+                used: false,
+            }])
+            .build()
+            .unwrap();
+        let seen = Arc::new(Mutex::new(None));
+        game.agents[0] = Some(Box::new(Reroller(seen.clone())));
+        let action = game.agent_action(0);
+        assert!(matches!(action.idx, ActionType::UseTool(0)), "{action:?}");
+        game.state.take_turn(&action).unwrap();
+        let face = game.state.draft_pool[0].rolled().map(|d| d.face);
+        if *seen.lock().unwrap() == face {
+            foreseen += 1;
+        }
+    }
+    // An agent given the live RNG would see every reroll coming.
+    assert!(foreseen < 10, "{foreseen}");
+}
+
+#[test]
+fn configured_game() {
+    let players = vec![
+        PlayerInfo::human("foo".into()),
+        PlayerInfo::ai("bot".into(), 1),
+    ];
+    let params = r#"{"seed": 7, "tools": ["FlipDraftedDie",
+        "RerollDraftedDie", "BumpDraftedDie"], "rules": {"tool_cost": 2}}"#;
+    let game: StainedAPI = GameAPI::init(&players, Some(params)).unwrap();
+    let view: serde_json::Value =
+        serde_json::from_str(&game.player_view("foo").unwrap()).unwrap();
+    assert_eq!(view["config"]["seed"], 7);
+    assert_eq!(view["config"]["rules"]["tool_cost"], 2);
+    assert_eq!(view["game"]["tools"][1]["tool_type"], "RerollDraftedDie");
+    assert_eq!(view["game"]["tools"][1]["cost"], 2);
+
+    let bad = r#"{"tools": ["FlipDraftedDie"]}"#;
+    let err = StainedAPI::init(&players, Some(bad)).err().unwrap();
+    assert!(err.to_string().contains("expected 3 tools"), "{err}");
+}
+
+#[test]
+fn solo_game() {
+    let players = vec![PlayerInfo::ai("solo".into(), 1)];
+    // Difficulty is set by the number of tools: fewer tools is harder.
+    let params = r#"{"seed": 3, "rules": {"num_tools": 2}}"#;
+    let mut game: StainedAPI = GameAPI::init(&players, Some(params)).unwrap();
+    game.start(1, |_, _| {}).unwrap();
+    assert!(game.is_game_over());
+    let fs: serde_json::Value =
+        serde_json::from_str(&game.final_state().unwrap()).unwrap();
+    assert_eq!(fs["game"]["tools"].as_array().unwrap().len(), 2);
+    assert_eq!(fs["scores"][0]["target"], game.state.solo_target());
+
+    let hard = r#"{"rules": {"num_tools": 6}}"#;
+    let err = StainedAPI::init(&players, Some(hard)).err().unwrap();
+    assert!(err.to_string().contains("at most 5 tools"), "{err}");
+}
+
+#[test]
+fn simultaneous_selection() {
+    let players = vec![
+        PlayerInfo::human("foo".into()),
+        PlayerInfo::ai("bot".into(), 1),
+        PlayerInfo::human("bar".into()),
+    ];
+    let params = r#"{"seed": 5, "rules": {"simultaneous_selection": true}}"#;
+    let mut game: StainedAPI = GameAPI::init(&players, Some(params)).unwrap();
+    game.start(1, |_, _| {}).unwrap();
+    let view = |game: &StainedAPI, id: &str| -> serde_json::Value {
+        serde_json::from_str(&game.player_view(id).unwrap()).unwrap()
+    };
+    // The AI picks right away, but its pick is hidden from the humans.
+    let foo = view(&game, "foo");
+    assert_eq!(foo["to_act"], serde_json::json!(["foo", "bar"]));
+    assert!(foo["game"]["players"][1]["template"].is_null());
+    assert!(foo["game"]["players"][1]["templates"].is_null());
+    assert!(foo["game"]["players"][0]["templates"].is_array());
+
+    let mut notified = vec![];
+    let pick = r#"{"player_id": "bar", "idx": {"SelectTemplate": 1}}"#;
+    game.process_action(pick, |id, _| notified.push(id.to_string()))
+        .unwrap();
+    assert_eq!(notified, ["foo", "bar"]);
+    assert!(game.process_action(pick, |_, _| {}).is_err());
+    let bar = view(&game, "bar");
+    assert_eq!(bar["to_act"], serde_json::json!(["foo"]));
+    assert!(bar["game"]["players"][2]["template"]["name"].is_string());
+    assert!(view(&game, "foo")["game"]["players"][2]["template"].is_null());
+
+    // Once everyone has picked, all templates are revealed.
+    let pick = r#"{"player_id": "foo", "idx": {"SelectTemplate": 0}}"#;
+    game.process_action(pick, |_, _| {}).unwrap();
+    let foo = view(&game, "foo");
+    assert_ne!(foo["game"]["phase"], "SelectTemplate");
+    for player in foo["game"]["players"].as_array().unwrap() {
+        assert!(player["template"]["name"].is_string(), "{player}");
+    }
+}
+
+#[test]
+fn delayed_spectators() {
+    let players = vec![
+        PlayerInfo::human("foo".into()),
+        PlayerInfo::ai("bot".into(), 1),
+    ];
+    let params = r#"{"seed": 2, "spectator_delay": 2}"#;
+    let mut game: StainedAPI = GameAPI::init(&players, Some(params)).unwrap();
+    game.add_spectator("tv".into()).unwrap();
+    let mut log: Vec<(String, String)> = vec![];
+    let views = |log: &[(String, String)], to: &str| -> Vec<String> {
+        log.iter()
+            .filter(|(id, msg)| {
+                id == to && !msg.contains(r#""action": "start""#)
+            })
+            .map(|(_, msg)| msg.clone())
+            .collect()
+    };
+    game.start(1, |id, msg| log.push((id.into(), msg.into())))
+        .unwrap();
+    while !game.is_game_over() {
+        let action = fallback_action(&game.state);
+        let json = serde_json::to_string(&action).unwrap();
+        game.process_action(&json, |id, msg| log.push((id.into(), msg.into())))
+            .unwrap();
+        let (actions, shown) = (views(&log, "foo"), views(&log, "tv"));
+        if !game.is_game_over() {
+            // Spectators lag two actions behind, and see no secrets.
+            assert_eq!(shown.len(), actions.len().saturating_sub(2));
+            if let Some(last) = shown.last() {
+                assert_eq!(last, &actions[actions.len() - 3]);
+            }
+            let view = game.spectator_view().unwrap();
+            assert!(!view.contains("secret"), "{view}");
+            assert!(!view.contains("seed"), "{view}");
+            assert!(game.player_view("foo").unwrap().contains("secret"));
+        }
+    }
+    // Spectators catch up when the game ends.
+    assert_eq!(views(&log, "tv").len(), views(&log, "foo").len());
+    assert!(game.spectator_view().unwrap().contains("secret"));
+}
+
+#[test]
+fn event_notices() {
+    let players = vec![
+        PlayerInfo::human("foo".into()),
+        PlayerInfo::ai("bot".into(), 1),
+    ];
+    let mut game: StainedAPI =
+        GameAPI::init(&players, Some(r#"{"seed": 3}"#)).unwrap();
+    game.start(1, |_, _| {}).unwrap();
+    // Track the draft pool from events alone, as a client would.
+    let id_of = |die: &serde_json::Value| {
+        die.as_str().unwrap().split_once('#').unwrap().1.to_string()
+    };
+    let mut pool: Vec<serde_json::Value> = vec![];
+    let mut rounds = 0;
+    let mut kinds = std::collections::BTreeSet::new();
+    while !game.is_game_over() {
+        let action = fallback_action(&game.state);
+        let json = serde_json::to_string(&action).unwrap();
+        let mut notices = vec![];
+        game.process_action(&json, |id, msg| {
+            if id == "foo" {
+                notices.push(msg.to_string());
+            }
+        })
+        .unwrap();
+        for notice in notices {
+            assert!(!notice.contains(r#""game""#), "{notice}");
+            let notice: serde_json::Value =
+                serde_json::from_str(&notice).unwrap();
+            for event in notice["events"].as_array().unwrap() {
+                let (kind, body) =
+                    event.as_object().unwrap().iter().next().unwrap();
+                kinds.insert(kind.clone());
+                match kind.as_str() {
+                    "DiceRolled" => {
+                        pool.extend(body["dice"].as_array().unwrap().clone())
+                    }
+                    "DieDrafted" => {
+                        let id = id_of(&body["die"]);
+                        pool.retain(|die| id_of(die) != id);
+                    }
+                    "DieReturned" => {
+                        let id = id_of(&body["die"]);
+                        for die in pool.iter_mut() {
+                            if id_of(die) == id {
+                                *die = body["die"].clone();
+                            }
+                        }
+                    }
+                    "ToolUsed" => {
+                        pool = body["draft_pool"].as_array().unwrap().clone()
+                    }
+                    "RoundEnded" => {
+                        rounds += 1;
+                        assert_eq!(body["round"], rounds);
+                        pool.clear();
+                    }
+                    "GameEnded" => {
+                        let standings = body["standings"].as_array().unwrap();
+                        assert_eq!(standings.len(), 2);
+                        assert_eq!(body["scores"].as_array().unwrap().len(), 2);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let view: serde_json::Value =
+            serde_json::from_str(&game.player_view("foo").unwrap()).unwrap();
+        assert_eq!(view["game"]["draft_pool"], serde_json::json!(pool));
+    }
+    assert_eq!(rounds, crate::constants::DEFAULT_NUM_ROUNDS);
+    for kind in ["TemplateSelected", "TurnStarted", "DieDrafted", "GameEnded"] {
+        assert!(kinds.contains(kind), "{kind}");
+    }
+}
+
+#[test]
+fn legal_and_last_actions_in_view() {
+    let players = vec![
+        PlayerInfo::human("foo".into()),
+        PlayerInfo::human("bar".into()),
+    ];
+    let params = r#"{"seed": 4, "show_legal_actions": true}"#;
+    let mut game: StainedAPI = GameAPI::init(&players, Some(params)).unwrap();
+    game.start(1, |_, _| {}).unwrap();
+    let view = |game: &StainedAPI, id: &str| -> serde_json::Value {
+        serde_json::from_str(&game.player_view(id).unwrap()).unwrap()
+    };
+    let (me, other) = match game.current_player_id() {
+        "foo" => ("foo", "bar"),
+        _ => ("bar", "foo"),
+    };
+    let mine = view(&game, me);
+    assert!(mine["last_action"].is_null());
+    assert!(view(&game, other)["legal_actions"].is_null());
+    let templates = &mine["legal_actions"]["templates"];
+    game.process_action(&templates[1].to_string(), |_, _| {})
+        .unwrap();
+    let last = &view(&game, other)["last_action"];
+    assert_eq!(last["player_id"], me);
+    assert!(
+        last["description"]
+            .as_str()
+            .unwrap()
+            .starts_with("selected")
+    );
+
+    // Placements are grouped by die, and each of them is legal.
+    let pick = r#"{"idx": {"SelectTemplate": 0}}"#;
+    game.process_action(pick, |_, _| {}).unwrap();
+    let id = game.current_player_id().to_string();
+    let legal = &view(&game, &id)["legal_actions"];
+    let dice = legal["dice"].as_array().unwrap();
+    assert!(!dice.is_empty());
+    assert_eq!(legal["pass"].as_array().unwrap().len(), 1);
+    for group in dice {
+        for action in group["actions"].as_array().unwrap() {
+            assert_eq!(
+                action["die_id"].to_string(),
+                group["die"].as_str().unwrap().split_once('#').unwrap().1
+            );
+            assert!(action["coords"].is_array());
+        }
+    }
+    let action = &dice[0]["actions"][0];
+    game.process_action(&action.to_string(), |_, _| {}).unwrap();
+    let last = &view(&game, &id)["last_action"];
+    assert_eq!(last["action"], *action);
+    let die = dice[0]["die"].as_str().unwrap().split_once('#').unwrap().0;
+    let description = last["description"].as_str().unwrap();
+    assert!(description.starts_with(&format!("placed {die} at")));
+}
+
+#[test]
+fn turn_timers() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    let players = vec![
+        PlayerInfo::human("foo".into()),
+        PlayerInfo::human("bar".into()),
+    ];
+    let params = r#"{"seed": 6, "turn_time_ms": 1000, "game_time_ms": 5000}"#;
+    let mut game: StainedAPI = GameAPI::init(&players, Some(params)).unwrap();
+    let now = Arc::new(AtomicU64::new(0));
+    let clock = now.clone();
+    game.set_clock(move || clock.load(Ordering::Relaxed));
+    let wait = |ms: u64| now.fetch_add(ms, Ordering::Relaxed);
+    game.start(1, |_, _| {}).unwrap();
+    let view = |game: &StainedAPI| -> serde_json::Value {
+        serde_json::from_str(&game.player_view("foo").unwrap()).unwrap()
+    };
+    let first = game.current_player_id().to_string();
+    let seat = |id: &str| usize::from(id != "foo");
+    wait(400);
+    let time_left = &view(&game)["time_left"][seat(&first)];
+    assert_eq!(time_left["turn_ms"], 600);
+    assert_eq!(time_left["game_ms"], 4600);
+
+    // A template is picked for a player who runs out of time.
+    wait(700);
+    game.check_clocks(|_, _| {}).unwrap();
+    let view_now = view(&game);
+    assert_eq!(view_now["last_action"]["player_id"], first.as_str());
+    assert_eq!(view_now["time_left"][seat(&first)]["game_ms"], 3900);
+    assert!(view_now["time_left"][seat(&first)]["turn_ms"].is_null());
+
+    // A late action is rejected, since a move was already made.
+    let second = game.current_player_id().to_string();
+    assert_ne!(second, first);
+    wait(1000);
+    let late = format!(
+        r#"{{"player_id": "{second}", "idx": {{"SelectTemplate": 0}}}}"#
+    );
+    let err = game.process_action(&late, |_, _| {}).unwrap_err();
+    assert!(err.to_string().contains("Out of time"), "{err}");
+
+    // Players who time out while drafting pass, and once both have used
+    // up their game time, the rest of the game plays itself.
+    wait(1000);
+    game.check_clocks(|_, _| {}).unwrap();
+    assert_eq!(view(&game)["last_action"]["description"], "passed");
+    for _ in 0..2 {
+        assert!(!game.is_game_over());
+        wait(5000);
+        game.check_clocks(|_, _| {}).unwrap();
+    }
+    assert!(game.is_game_over());
+}
+
+#[test]
+fn tool_use_keeps_turn_clock() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    let players = vec![
+        PlayerInfo::human("foo".into()),
+        PlayerInfo::human("bar".into()),
+    ];
+    let params = r#"{"seed": 6, "turn_time_ms": 1000,
+        "tools": ["FlipDraftedDie", "RerollDraftedDie", "BumpDraftedDie"]}"#;
+    let mut game: StainedAPI = GameAPI::init(&players, Some(params)).unwrap();
+    let now = Arc::new(AtomicU64::new(0));
+    let clock = now.clone();
+    game.set_clock(move || clock.load(Ordering::Relaxed));
+    game.start(1, |_, _| {}).unwrap();
+    let pick = r#"{"idx": {"SelectTemplate": 0}}"#;
+    for _ in 0..2 {
+        game.process_action(pick, |_, _| {}).unwrap();
+    }
+    let id = game.current_player_id().to_string();
+    let seat = usize::from(id != "foo");
+    now.fetch_add(300, Ordering::Relaxed);
+    let flip = r#"{"idx": {"UseTool": 0}, "coords": null,
+        "tool": {"FlipDraftedDie": {"draft_idx": 0}}}"#;
+    game.process_action(flip, |_, _| {}).unwrap();
+    assert_eq!(game.current_player_id(), id);
+    let view: serde_json::Value =
+        serde_json::from_str(&game.player_view("foo").unwrap()).unwrap();
+    assert_eq!(view["time_left"][seat]["turn_ms"], 700);
+}
+
+#[test]
+fn seat_takeover() {
+    let players = vec![
+        PlayerInfo::human("foo".into()),
+        PlayerInfo::human("bar".into()),
+    ];
+    let mut game: StainedAPI =
+        GameAPI::init(&players, Some(r#"{"seed": 8}"#)).unwrap();
+    game.start(1, |_, _| {}).unwrap();
+    let leaver = game.current_player_id().to_string();
+    let stayer = if leaver == "foo" { "bar" } else { "foo" };
+    let mut log: Vec<(String, String)> = vec![];
+    game.set_player_ai(&leaver, Some(1), |id, msg| {
+        log.push((id.into(), msg.into()))
+    })
+    .unwrap();
+    // The AI takes the seat's turn right away, and only the remaining human
+    // is told.
+    assert!(log.iter().all(|(id, _)| id == stayer));
+    assert!(log[0].1.contains("SeatChanged"), "{}", log[0].1);
+    assert!(log.len() > 1);
+    assert_eq!(game.current_player_id(), stayer);
+    assert!(game.set_player_ai("nobody", None, |_, _| {}).is_err());
+    // Seats that already have the requested controller don't change.
+    let mut log: Vec<String> = vec![];
+    game.set_player_ai(&leaver, Some(1), |_, msg| log.push(msg.into()))
+        .unwrap();
+    game.set_player_ai(stayer, None, |_, msg| log.push(msg.into()))
+        .unwrap();
+    assert!(log.is_empty(), "{log:?}");
+    assert_eq!(game.seat_changes.len(), 1);
+
+    // The human takes the seat back halfway through.
+    let mut actions = 0;
+    while !game.is_game_over() {
+        if actions == 20 {
+            game.set_player_ai(&leaver, None, |_, _| {}).unwrap();
+        }
+        let action = fallback_action(&game.state);
+        let json = serde_json::to_string(&action).unwrap();
+        game.process_action(&json, |_, _| {}).unwrap();
+        actions += 1;
+    }
+    let fs: serde_json::Value =
+        serde_json::from_str(&game.final_state().unwrap()).unwrap();
+    let changes = fs["seat_changes"].as_array().unwrap();
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0]["ai_level"], 1);
+    assert_eq!(changes[0]["round"], 0);
+    assert!(changes[1]["ai_level"].is_null());
+    let restored = StainedAPI::restore(&players, &fs.to_string()).unwrap();
+    assert_eq!(restored.seat_changes.len(), 2);
+}
+
+#[test]
+fn hints_for_players() {
+    let players = vec![
+        PlayerInfo::human("foo".into()),
+        PlayerInfo::ai("bot".into(), 1),
+    ];
+    let mut game: StainedAPI =
+        GameAPI::init(&players, Some(r#"{"seed": 9}"#)).unwrap();
+    game.start(1, |_, _| {}).unwrap();
+    let pick = r#"{"idx": {"SelectTemplate": 0}}"#;
+    while game.state.phase == crate::turn::TurnPhase::SelectTemplate {
+        game.process_action(pick, |_, _| {}).unwrap();
+    }
+    assert!(game.hints("bot", 0, 3).is_err());
+    let hints: serde_json::Value =
+        serde_json::from_str(&game.hints("foo", 0, 3).unwrap()).unwrap();
+    let hints = hints.as_array().unwrap();
+    assert_eq!(hints.len(), 3);
+    let deltas: Vec<f64> = hints
+        .iter()
+        .map(|h| h["score_delta"].as_f64().unwrap())
+        .collect();
+    assert!(deltas.windows(2).all(|w| w[0] >= w[1]), "{deltas:?}");
+    // The best hint is what the AI would play.
+    let mut state = game.state.clone();
+    state.redact_secrets(0);
+    let action = create_agent(1).choose_action(&state);
+    assert_eq!(hints[0]["action"], serde_json::to_value(&action).unwrap());
+}
@@ -1,14 +1,36 @@
 use crate::color::Dice;
+use crate::constants::{BOARD_COLS, BOARD_ROWS};
 use crate::template::Slot;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+use std::str::FromStr;
+
+pub type Board = [[BoardCell; BOARD_COLS]; BOARD_ROWS];
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "RawCell")]
 pub struct BoardCell {
     pub slot: Slot,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub die: Option<Dice>,
 }
+#[derive(Deserialize)]
+struct RawCell {
+    slot: Slot,
+    #[serde(default)]
+    die: Option<Dice>,
+}
+impl TryFrom<RawCell> for BoardCell {
+    type Error = String;
+    fn try_from(raw: RawCell) -> Result<Self, Self::Error> {
+        let cell = Self {
+            slot: raw.slot,
+            die: raw.die,
+        };
+        cell.check()?;
+        Ok(cell)
+    }
+}
 impl Default for BoardCell {
     fn default() -> Self {
         Self {
@@ -33,4 +55,126 @@ impl BoardCell {
             die: Some(die),
         }
     }
+    // A placed die must satisfy the slot.
+    fn check(&self) -> Result<(), String> {
+        match self.die {
+            Some(die) if !self.slot.accepts(die) => {
+                Err(format!("Die {die} doesn't fit slot {}", self.slot))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+impl FromStr for BoardCell {
+    type Err = String;
+    // Accepts either a slot (`R_`, `_4`, `__`) or a placed die (`B5`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(slot) = s.parse::<Slot>() {
+            return Ok(Self { slot, die: None });
+        }
+        let cell = s
+            .parse::<Dice>()
+            .map(Self::with_die)
+            .map_err(|_| format!("Invalid board cell: {s}"))?;
+        cell.check()?;
+        Ok(cell)
+    }
+}
+
+// Parses a board from the grid printed by `Player::pretty_print`: one line
+// per row, with cells separated by whitespace. Blank lines are ignored.
+pub fn parse_board(text: &str) -> Result<Board, String> {
+    parse_grid(text.lines())
+}
+
+// Splits leading `Key: value` lines from the grid lines that follow them.
+pub(crate) fn split_headers(
+    text: &str,
+) -> (Vec<(&str, &str)>, impl Iterator<Item = &str>) {
+    let mut lines = text.lines().map(str::trim).peekable();
+    let mut headers = Vec::new();
+    while let Some(line) = lines.next_if(|l| l.is_empty() || l.contains(':')) {
+        if let Some((key, val)) = line.split_once(':') {
+            headers.push((key.trim(), val.trim()));
+        }
+    }
+    (headers, lines)
+}
+
+pub(crate) fn parse_grid<'a, T: FromStr<Err = String>>(
+    lines: impl Iterator<Item = &'a str>,
+) -> Result<[[T; BOARD_COLS]; BOARD_ROWS], String> {
+    let rows = lines
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let cells = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<T>, _>>()?;
+            cells.try_into().map_err(|_| {
+                format!("Expected {BOARD_COLS} cells per row: {line}")
+            })
+        })
+        .collect::<Result<Vec<[T; BOARD_COLS]>, String>>()?;
+    rows.try_into()
+        .map_err(|_| format!("Expected {BOARD_ROWS} rows"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Player;
+    use crate::template::BoardTemplate;
+
+    #[test]
+    fn test_parse_cells() {
+        assert!(matches!("__".parse::<BoardCell>(), Ok(c) if c.die.is_none()));
+        let cell: BoardCell = "B5".parse().unwrap();
+        assert_eq!(cell.to_string(), "B5");
+        assert!("B7".parse::<BoardCell>().is_err());
+        assert!("X_".parse::<BoardCell>().is_err());
+        assert!("_0".parse::<BoardCell>().is_err());
+    }
+
+    #[test]
+    fn test_parse_player() {
+        let text = "Tokens: 2
+            R_ _6 __ __ Y3
+            __ _3 B_ __ P2
+            __ _5 _6 _2 G5
+            __ _4 __ _1 R4
+        ";
+        let player: Player = text.parse().unwrap();
+        // Printing the player gives back the same grid.
+        let reparsed: Player = player.to_string().parse().unwrap();
+        assert_eq!(player.to_string(), reparsed.to_string());
+        assert!(
+            player
+                .to_string()
+                .starts_with("Tokens: 2\nR_ _6 __ __ Y3 \n")
+        );
+        let short = text.replace("R4\n", "\n");
+        assert!(short.parse::<Player>().unwrap_err().contains("5 cells"));
+        assert!(text.replace("Tokens", "Tokes").parse::<Player>().is_err());
+    }
+
+    #[test]
+    fn test_parse_template() {
+        let template: BoardTemplate = "name: Test
+            value: 5
+            __ __ _6 __ __
+            __ _5 B_ _4 __
+            _3 G_ Y_ P_ _2
+            _1 _4 R_ _5 _3"
+            .parse()
+            .unwrap();
+        assert_eq!(template.value, 5);
+        assert_eq!(template.info.name, "Test");
+        assert_eq!(template.slots[2][1].to_string(), "G_");
+        let err = "value: 3\n__ __\n".parse::<BoardTemplate>().unwrap_err();
+        assert!(err.contains("5 cells"), "{err}");
+        assert!("value: 9\n".parse::<BoardTemplate>().is_err());
+        assert!("Tokens: 5\n".parse::<BoardTemplate>().is_err());
+    }
 }
@@ -50,23 +50,70 @@ pub const ALL_COLORS: [Color; NUM_COLORS] = [
     Color::Purple,
 ];
 
+// Identifies one physical die for the whole game. The bag starts with the
+// colors in `ALL_COLORS` order repeated, so each id has a fixed color.
+pub type DieId = u16;
+pub fn die_color(id: DieId) -> Color {
+    ALL_COLORS[id as usize % NUM_COLORS]
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Dice {
     pub color: Color,
     pub face: u8,
+    // None for hypothetical dice that aren't in the game.
+    id: Option<DieId>,
 }
+// Dice are written as a color and a face, e.g. `B5`.
 impl Display for Dice {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}{}", self.color, self.face)
     }
 }
+// Serialized dice also carry their id, if any, e.g. `B5#12`.
 impl Serialize for Dice {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        let s = format!("{}{}", self.color, self.face);
-        serializer.serialize_str(&s)
+        match self.id {
+            Some(id) => serializer.collect_str(&format_args!("{self}#{id}")),
+            None => serializer.collect_str(self),
+        }
+    }
+}
+// Splits the optional `#id` suffix off a die.
+fn split_id(s: &str) -> Result<(&str, Option<DieId>), String> {
+    match s.split_once('#') {
+        Some((die, id)) => {
+            let id = id
+                .parse()
+                .map_err(|_| format!("Invalid die {s:?}: bad id {id:?}"))?;
+            Ok((die, Some(id)))
+        }
+        None => Ok((s, None)),
+    }
+}
+impl FromStr for Dice {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (die, id) = split_id(s)?;
+        let (color, face) = die
+            .split_at_checked(1)
+            .filter(|(_, face)| face.len() == 1)
+            .ok_or_else(|| {
+                format!("Invalid die {s:?}: expected two characters")
+            })?;
+        let color = color.parse::<Color>().map_err(|_| {
+            format!("Invalid die {s:?}: unknown color {color:?}")
+        })?;
+        let face = match face.as_bytes() {
+            [face @ b'1'..=b'6'] => face - b'0',
+            _ => {
+                return Err(format!("Invalid die {s:?}: face must be 1 to 6"));
+            }
+        };
+        Ok(Dice { color, face, id })
     }
 }
 impl<'de> Deserialize<'de> for Dice {
@@ -74,25 +121,32 @@ impl<'de> Deserialize<'de> for Dice {
     where
         D: serde::Deserializer<'de>,
     {
-        let s: &str = Deserialize::deserialize(deserializer)?;
-        if s.len() != 2 {
-            return Err(serde::de::Error::custom(format!(
-                "Invalid dice format: {s}"
-            )));
-        }
-        let color = s[0..1].parse::<Color>().map_err(|_| {
-            serde::de::Error::custom(format!("Invalid color: {s}"))
-        })?;
-        let face = s[1..2].parse::<u8>().map_err(|_| {
-            serde::de::Error::custom(format!("Invalid face: {s}"))
-        })?;
-        Ok(Dice { color, face })
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
     }
 }
 impl Dice {
-    pub fn roll(color: Color, rng: &mut impl rand::Rng) -> Self {
+    // A hypothetical die that isn't in the game.
+    pub fn new(color: Color, face: u8) -> Self {
+        Self {
+            color,
+            face,
+            id: None,
+        }
+    }
+    pub fn id(self) -> Option<DieId> {
+        self.id
+    }
+    pub(crate) fn id_mut(&mut self) -> &mut Option<DieId> {
+        &mut self.id
+    }
+    pub fn roll(id: DieId, rng: &mut impl rand::Rng) -> Self {
         let face = (1..=6).choose(rng).unwrap_or(1);
-        Self { color, face }
+        Self {
+            color: die_color(id),
+            face,
+            id: Some(id),
+        }
     }
     pub fn reroll(&mut self, rng: &mut impl rand::Rng) {
         self.face = (1..=6).choose(rng).unwrap_or(1);
@@ -108,15 +162,189 @@ impl Dice {
     }
 }
 
+/// A die in the draft pool. Dice drawn from the bag by a tool are `Wild`
+/// until the player who drew them picks a face.
+#[derive(Debug, Clone, Copy)]
+pub enum PoolDie {
+    Rolled(Dice),
+    Wild(Color, Option<DieId>),
+}
+impl From<Dice> for PoolDie {
+    fn from(die: Dice) -> Self {
+        PoolDie::Rolled(die)
+    }
+}
+// Wildcards are written with a `*` in place of the face, e.g. `B*`.
+impl Display for PoolDie {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PoolDie::Rolled(die) => write!(f, "{die}"),
+            PoolDie::Wild(color, _) => write!(f, "{color}*"),
+        }
+    }
+}
+impl Serialize for PoolDie {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            PoolDie::Rolled(die) => die.serialize(serializer),
+            PoolDie::Wild(_, Some(id)) => {
+                serializer.collect_str(&format_args!("{self}#{id}"))
+            }
+            PoolDie::Wild(_, None) => serializer.collect_str(self),
+        }
+    }
+}
+impl FromStr for PoolDie {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (die, id) = split_id(s)?;
+        match die.strip_suffix('*') {
+            Some(color) => color
+                .parse()
+                .map(|color| PoolDie::Wild(color, id))
+                .map_err(|_| format!("Invalid die {s:?}: unknown color")),
+            None => s.parse().map(PoolDie::Rolled),
+        }
+    }
+}
+// Games saved before wildcards had their own notation wrote them with a
+// face of 0, e.g. `B0`.
+fn legacy_wild(s: &str) -> Option<PoolDie> {
+    let (die, id) = split_id(s).ok()?;
+    let color = die.strip_suffix('0')?.parse().ok()?;
+    Some(PoolDie::Wild(color, id))
+}
+impl<'de> Deserialize<'de> for PoolDie {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        legacy_wild(&s)
+            .map_or_else(|| s.parse(), Ok)
+            .map_err(serde::de::Error::custom)
+    }
+}
+impl PoolDie {
+    pub fn color(self) -> Color {
+        match self {
+            PoolDie::Rolled(die) => die.color,
+            PoolDie::Wild(color, _) => color,
+        }
+    }
+    pub fn id(self) -> Option<DieId> {
+        match self {
+            PoolDie::Rolled(die) => die.id,
+            PoolDie::Wild(_, id) => id,
+        }
+    }
+    pub(crate) fn id_mut(&mut self) -> &mut Option<DieId> {
+        match self {
+            PoolDie::Rolled(die) => &mut die.id,
+            PoolDie::Wild(_, id) => id,
+        }
+    }
+    pub fn is_wild(self) -> bool {
+        matches!(self, PoolDie::Wild(..))
+    }
+    pub fn rolled(self) -> Option<Dice> {
+        match self {
+            PoolDie::Rolled(die) => Some(die),
+            PoolDie::Wild(..) => None,
+        }
+    }
+    // The die as it would be drafted. A face must be chosen for wildcards,
+    // and only for wildcards.
+    pub fn with_face(self, face: Option<u8>) -> Result<Dice, String> {
+        match (self, face) {
+            (PoolDie::Rolled(die), None) => Ok(die),
+            (PoolDie::Wild(color, id), Some(face @ 1..=6)) => {
+                Ok(Dice { color, face, id })
+            }
+            (PoolDie::Wild(..), Some(face)) => {
+                Err(format!("Invalid face {face}: must be 1 to 6"))
+            }
+            (PoolDie::Rolled(_), Some(_)) => {
+                Err("Cannot choose face for a non-wildcard die".into())
+            }
+            (PoolDie::Wild(..), None) => {
+                Err("Must choose face for a wildcard die".into())
+            }
+        }
+    }
+    // The die as it is, or with a random face if it's a wildcard.
+    pub fn roll_if_wild(self, rng: &mut impl rand::Rng) -> Dice {
+        match self {
+            PoolDie::Rolled(die) => die,
+            PoolDie::Wild(color, id) => {
+                let mut die = Dice { color, face: 1, id };
+                die.reroll(rng);
+                die
+            }
+        }
+    }
+    pub fn reroll(&mut self, rng: &mut impl rand::Rng) {
+        let mut die = Dice {
+            color: self.color(),
+            face: 1,
+            id: self.id(),
+        };
+        die.reroll(rng);
+        *self = PoolDie::Rolled(die);
+    }
+}
+
 #[test]
 fn test_dice_serialization() {
-    let die = Dice {
-        color: Color::Blue,
-        face: 5,
-    };
+    let die = Dice::new(Color::Blue, 5);
     let serialized = serde_json::to_string(&die).unwrap();
     assert_eq!(serialized, r#""B5""#);
     let deserialized: Dice = serde_json::from_str(&serialized).unwrap();
     assert_eq!(deserialized.color, Color::Blue);
     assert_eq!(deserialized.face, 5);
+    let die = Dice::roll(13, &mut rand::rng());
+    assert_eq!(die.color, Color::Blue);
+    let serialized = serde_json::to_string(&die).unwrap();
+    assert_eq!(serialized, format!(r#""B{}#13""#, die.face));
+    let deserialized: Dice = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized.id(), Some(13));
+    assert_eq!(deserialized.to_string(), die.to_string());
+}
+
+#[test]
+fn test_strict_dice_parsing() {
+    let parse = |json: &str| serde_json::from_str::<Dice>(json);
+    for bad in [r#""R9""#, r#""G*""#, r#""B0""#, r#""X1""#, r#""R12""#, "3"] {
+        assert!(parse(bad).is_err(), "{bad}");
+    }
+    for bad in [r#""R1#""#, r#""R1#x""#, r#""R1#-2""#] {
+        assert!(parse(bad).is_err(), "{bad}");
+    }
+    let err = parse(r#""R9""#).unwrap_err().to_string();
+    assert!(err.contains("face must be 1 to 6"), "{err}");
+}
+
+#[test]
+fn test_pool_dice() {
+    let parse = |json: &str| serde_json::from_str::<PoolDie>(json);
+    let wild = parse(r#""G*""#).unwrap();
+    assert!(wild.is_wild());
+    assert_eq!(serde_json::to_string(&wild).unwrap(), r#""G*""#);
+    let wild = parse(r#""G*#7""#).unwrap();
+    assert_eq!(wild.id(), Some(7));
+    assert_eq!(serde_json::to_string(&wild).unwrap(), r#""G*#7""#);
+    assert!(matches!(parse(r#""Y4""#), Ok(PoolDie::Rolled(_))));
+    for bad in [r#""X0""#, r#""X*""#, r#""*""#] {
+        assert!(parse(bad).is_err(), "{bad}");
+    }
+    // Saved games used to write wildcards with a face of 0.
+    let legacy = parse(r#""B0#3""#).unwrap();
+    assert_eq!(legacy.to_string(), "B*");
+    assert_eq!(legacy.id(), Some(3));
+    assert_eq!(wild.with_face(Some(3)).unwrap().face, 3);
+    assert!(wild.with_face(None).is_err());
+    assert!(wild.with_face(Some(7)).is_err());
 }
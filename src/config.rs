@@ -0,0 +1,314 @@
+use crate::constants::*;
+use crate::objective::{ALL_OBJECTIVES, Objective};
+use crate::template::{
+    BoardTemplate, builtin_template_cards, parse_template_cards,
+};
+use crate::tool::{ALL_TOOL_TYPES, ToolType};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+type DynError = Box<dyn std::error::Error>;
+
+/// Game setup options, parsed from the params passed to `GameAPI::init`.
+/// Every field is optional; anything left out is chosen at random.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<ToolType>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub objectives: Option<Vec<Objective>>,
+    // Two template card indices for each seat, in seat order.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub templates: Option<Vec<[usize; CARDS_PER_PLAYER]>>,
+    // More template cards in the format of `data/templates.txt`, numbered
+    // after the built-in ones. Only this game can deal them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra_templates: Option<String>,
+    // Longest time an AI may take to choose an action. Agents are given it
+    // as a deadline and return the best action found by then.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ai_time_budget_ms: Option<u64>,
+    // Number of actions spectators lag behind the game, so that they can't
+    // pass on what they see to the players.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spectator_delay: Option<usize>,
+    // Longest a human player may take over one turn, and over the whole
+    // game, before a move is made for them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turn_time_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_time_ms: Option<u64>,
+    // AI level that moves for players who run out of time. They pass, or
+    // pick a random template, if this isn't set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ai_level: Option<u8>,
+    // Include the viewer's legal actions in their view when it's their
+    // turn, so clients don't need their own copy of the placement rules.
+    pub show_legal_actions: bool,
+    pub rules: Rules,
+}
+
+/// Rule variants that change how the game is played or scored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rules {
+    // Tokens needed to use a tool for the first time; each later use costs
+    // twice as much.
+    pub tool_cost: u8,
+    // Points lost for each empty slot at the end of the game.
+    pub empty_slot_penalty: i32,
+    pub num_rounds: usize,
+    pub num_tools: usize,
+    // Defaults to two objectives in solo games and three otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_objectives: Option<usize>,
+    // All players pick their templates at once, without seeing each
+    // other's picks, instead of in turn order.
+    pub simultaneous_selection: bool,
+}
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            tool_cost: 1,
+            empty_slot_penalty: 1,
+            num_rounds: DEFAULT_NUM_ROUNDS,
+            num_tools: DEFAULT_NUM_TOOLS,
+            num_objectives: None,
+            simultaneous_selection: false,
+        }
+    }
+}
+
+impl Rules {
+    pub fn num_objectives(&self, num_players: usize) -> usize {
+        self.num_objectives.unwrap_or(if num_players == 1 {
+            SOLO_NUM_OBJECTIVES
+        } else {
+            DEFAULT_NUM_OBJECTIVES
+        })
+    }
+}
+
+impl GameConfig {
+    pub fn from_json(json: &str) -> Result<Self, DynError> {
+        serde_json::from_str(json)
+            .map_err(|e| format!("Invalid game config: {e}").into())
+    }
+    // The cards this game deals from: the built-in set followed by any
+    // `extra_templates`.
+    pub fn template_cards(
+        &self,
+    ) -> Result<Cow<'static, [[BoardTemplate; 2]]>, DynError> {
+        let builtin = builtin_template_cards();
+        let Some(text) = &self.extra_templates else {
+            return Ok(Cow::Borrowed(builtin));
+        };
+        let mut cards = builtin.to_vec();
+        for mut card in parse_template_cards(text)
+            .map_err(|e| format!("Invalid game config: {e}"))?
+        {
+            for side in &mut card {
+                side.info.card += builtin.len();
+            }
+            cards.push(card);
+        }
+        Ok(Cow::Owned(cards))
+    }
+    pub fn validate(&self, num_players: usize) -> Result<(), DynError> {
+        let invalid = |msg: String| -> Result<(), DynError> {
+            Err(format!("Invalid game config: {msg}").into())
+        };
+        let rules = &self.rules;
+        if !(1..=MAX_ROUNDS).contains(&rules.num_rounds) {
+            return invalid(format!(
+                "number of rounds must be between 1 and {MAX_ROUNDS}"
+            ));
+        }
+        if !(1..=ALL_TOOL_TYPES.len()).contains(&rules.num_tools) {
+            return invalid(format!(
+                "number of tools must be between 1 and {}",
+                ALL_TOOL_TYPES.len()
+            ));
+        }
+        if num_players == 1 && rules.num_tools > SOLO_MAX_TOOLS {
+            return invalid(format!(
+                "solo games use at most {SOLO_MAX_TOOLS} tools"
+            ));
+        }
+        let num_objectives = rules.num_objectives(num_players);
+        if !(1..=ALL_OBJECTIVES.len()).contains(&num_objectives) {
+            return invalid(format!(
+                "number of objectives must be between 1 and {}",
+                ALL_OBJECTIVES.len()
+            ));
+        }
+        if let Some(tools) = &self.tools {
+            if tools.len() != rules.num_tools {
+                return invalid(format!(
+                    "expected {} tools, got {}",
+                    rules.num_tools,
+                    tools.len()
+                ));
+            }
+            for (i, tool) in tools.iter().enumerate() {
+                if !ALL_TOOL_TYPES.contains(tool) {
+                    return invalid(format!("tool {tool:?} is not supported"));
+                }
+                if tools[..i].contains(tool) {
+                    return invalid(format!("duplicate tool {tool:?}"));
+                }
+            }
+        }
+        if let Some(objectives) = &self.objectives {
+            if objectives.len() != num_objectives {
+                return invalid(format!(
+                    "expected {} objectives, got {}",
+                    num_objectives,
+                    objectives.len()
+                ));
+            }
+            for (i, obj) in objectives.iter().enumerate() {
+                if obj.points() <= 0 {
+                    return invalid(format!(
+                        "objective {obj:?} must be worth at least one point"
+                    ));
+                }
+                if objectives[..i].iter().any(|o| {
+                    std::mem::discriminant(o) == std::mem::discriminant(obj)
+                }) {
+                    return invalid(format!("duplicate objective {obj:?}"));
+                }
+            }
+        }
+        let num_cards = self.template_cards()?.len();
+        if let Some(templates) = &self.templates {
+            if templates.len() != num_players {
+                return invalid(format!(
+                    "expected templates for {num_players} players, got {}",
+                    templates.len()
+                ));
+            }
+            let cards: Vec<usize> =
+                templates.iter().flatten().copied().collect();
+            for (i, card) in cards.iter().enumerate() {
+                if *card >= num_cards {
+                    return invalid(format!(
+                        "template card {card} does not exist"
+                    ));
+                }
+                if cards[..i].contains(card) {
+                    return invalid(format!(
+                        "template card {card} is dealt twice"
+                    ));
+                }
+            }
+        }
+        if self.ai_time_budget_ms == Some(0) {
+            return invalid("AI time budget must be positive".into());
+        }
+        if rules.tool_cost == 0 {
+            return invalid("tool cost must be positive".into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() -> Result<(), DynError> {
+        let config = GameConfig::from_json(
+            r#"{
+                "seed": 42,
+                "tools": ["FlipDraftedDie", "RerollDraftedDie", "BumpDraftedDie"],
+                "objectives": [{"RowColors": 6}, {"Pair12": 2}, {"Colors": 4}],
+                "templates": [[0, 1], [2, 3]],
+                "rules": {"tool_cost": 2}
+            }"#,
+        )?;
+        config.validate(2)?;
+        assert_eq!(config.seed, Some(42));
+        assert_eq!(config.rules.tool_cost, 2);
+        assert_eq!(config.rules.empty_slot_penalty, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extra_templates() -> Result<(), DynError> {
+        let rows = "__ __ __ __ __\n".repeat(BOARD_ROWS);
+        let card =
+            format!("name: A\nvalue: 4\n{rows}name: B\nvalue: 5\n{rows}");
+        let extra = builtin_template_cards().len();
+        let config = GameConfig {
+            templates: Some(vec![[extra, 0], [1, 2]]),
+            extra_templates: Some(card),
+            ..Default::default()
+        };
+        let game = crate::game::GameState::init_with_config(2, &config)?;
+        let template = &game.players[0].templates[0];
+        assert_eq!(
+            (template.info.name.as_str(), template.info.card),
+            ("A", extra)
+        );
+        // Other games only see the built-in cards.
+        let config = GameConfig {
+            extra_templates: None,
+            ..config
+        };
+        assert!(
+            config
+                .validate(2)
+                .unwrap_err()
+                .to_string()
+                .contains("does not exist")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let check = |json: &str| {
+            GameConfig::from_json(json)
+                .and_then(|c| c.validate(2))
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(check(r#"{"sed": 1}"#).contains("unknown field `sed`"));
+        assert!(
+            check(r#"{"tools": ["FlipDraftedDie"]}"#)
+                .contains("expected 3 tools")
+        );
+        assert!(
+            check(r#"{"tools": ["DraftTwoDice", "FlipDraftedDie", "RerollDraftedDie"]}"#)
+                .contains("not supported")
+        );
+        assert!(
+            check(r#"{"objectives": [{"Pair12": 2}, {"Pair12": 3}, {"Colors": 4}]}"#)
+                .contains("duplicate objective")
+        );
+        assert!(
+            check(r#"{"templates": [[0, 1], [1, 2]]}"#).contains("dealt twice")
+        );
+        assert!(check(r#"{"templates": [[0, 1]]}"#).contains("for 2 players"));
+        assert!(check(r#"{"rules": {"tool_cost": 0}}"#).contains("tool cost"));
+        assert!(
+            check(r#"{"extra_templates": "name: A"}"#)
+                .contains("`value:` header")
+        );
+        assert!(
+            check(r#"{"rules": {"num_rounds": 11}}"#)
+                .contains("number of rounds")
+        );
+        assert!(
+            check(
+                r#"{"rules": {"num_tools": 2}, "tools": ["FlipDraftedDie"]}"#
+            )
+            .contains("expected 2 tools")
+        );
+    }
+}
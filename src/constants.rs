@@ -1,9 +1,36 @@
-pub const NUM_ROUNDS: usize = 10;
-pub const NUM_OBJECTIVES: usize = 3;
-pub const NUM_TOOLS: usize = 3;
-pub const MAX_PLAYERS: usize = 4;
+// Defaults for the per-game settings in `config::Rules`.
+pub const DEFAULT_NUM_ROUNDS: usize = 10;
+pub const DEFAULT_NUM_OBJECTIVES: usize = 3;
+pub const DEFAULT_NUM_TOOLS: usize = 3;
+pub const MAX_PLAYERS: usize = 6;
+// Tables this large use the expansion's split draft pool.
+pub const EXPANSION_PLAYERS: usize = 5;
+// Solo games draft from a pool of four dice and use at most five tools.
+pub const SOLO_POOL_SIZE: usize = 4;
+pub const SOLO_MAX_TOOLS: usize = 5;
+pub const SOLO_EMPTY_SLOT_PENALTY: i32 = 3;
+pub const SOLO_NUM_OBJECTIVES: usize = 2;
+// Each player is dealt this many template cards, with a template on each
+// side.
+pub const CARDS_PER_PLAYER: usize = 2;
+pub const TEMPLATES_PER_PLAYER: usize = 2 * CARDS_PER_PLAYER;
 pub const NUM_COLORS: usize = 5;
-pub const DICE_PER_COLOR: usize =
-    (2 * MAX_PLAYERS + 1) * NUM_ROUNDS / NUM_COLORS;
 pub const BOARD_ROWS: usize = 4;
 pub const BOARD_COLS: usize = 5;
+// Each player drafts two dice per round, so more rounds than this would
+// leave nowhere to place them.
+pub const MAX_ROUNDS: usize = BOARD_ROWS * BOARD_COLS / 2;
+
+// The base game's bag of 90 dice is sized for this many players; bigger
+// tables get more dice.
+pub const BASE_BAG_PLAYERS: usize = 4;
+
+// Enough dice of each color to fill every draft pool in the game.
+pub const fn dice_per_color(num_players: usize, num_rounds: usize) -> usize {
+    let players = if num_players > BASE_BAG_PLAYERS {
+        num_players
+    } else {
+        BASE_BAG_PLAYERS
+    };
+    ((2 * players + 1) * num_rounds).div_ceil(NUM_COLORS)
+}
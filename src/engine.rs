@@ -0,0 +1,305 @@
+use crate::agent::{Agent, fallback_action, legal_actions};
+use crate::game::GameState;
+use crate::turn::TurnAction;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, channel};
+use std::time::{Duration, Instant};
+
+type DynError = Box<dyn std::error::Error>;
+
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Messages sent to the engine, one JSON object per line.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Request<'a> {
+    // Sent once at startup; the engine must reply with `Ready`.
+    Hello {
+        protocol: u32,
+    },
+    // The engine must reply with a `TurnAction` for `player`.
+    Turn {
+        player: usize,
+        game: &'a GameState,
+        legal_actions: &'a [TurnAction],
+    },
+    // Sent when the agent is dropped; no reply is expected.
+    Quit,
+}
+
+/// A request with its sequence number, which the reply must echo.
+#[derive(Serialize)]
+struct Numbered<'a> {
+    seq: u64,
+    #[serde(flatten)]
+    request: &'a Request<'a>,
+}
+
+/// The sequence number of a reply; its other fields are parsed later.
+#[derive(Deserialize)]
+struct Seq {
+    seq: u64,
+}
+
+/// Reply to `Request::Hello`.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Ready {
+    Ready { name: String },
+}
+
+/// Agent that runs in a separate process, similar in spirit to a UCI chess
+/// engine. Bad replies and timeouts fall back to a random legal action.
+pub struct ExternalAgent {
+    name: String,
+    timeout: Duration,
+    failures: AtomicUsize,
+    engine: Mutex<Engine>,
+}
+
+/// The engine's process, which is stopped when this is dropped.
+struct Engine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    seq: u64,
+}
+impl Engine {
+    // Sends a request and returns the sequence number its reply must echo.
+    fn send(&mut self, request: &Request) -> Result<u64, DynError> {
+        self.seq += 1;
+        let numbered = Numbered {
+            seq: self.seq,
+            request,
+        };
+        writeln!(self.stdin, "{}", serde_json::to_string(&numbered)?)?;
+        self.stdin.flush()?;
+        Ok(self.seq)
+    }
+    // Waits for the reply to request `seq`. Late replies to earlier
+    // requests, and lines without a sequence number, are discarded.
+    fn recv(
+        &mut self,
+        seq: u64,
+        timeout: Duration,
+    ) -> Result<String, DynError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let line = match self.lines.recv_timeout(left) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err("Engine timed out".into());
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err("Engine closed its output".into());
+                }
+            };
+            if serde_json::from_str::<Seq>(&line).is_ok_and(|r| r.seq == seq) {
+                return Ok(line);
+            }
+        }
+    }
+}
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = self.send(&Request::Quit);
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl ExternalAgent {
+    // Starts the engine and waits (up to `timeout`) for its handshake.
+    pub fn spawn(
+        program: &str,
+        args: &[&str],
+        timeout: Duration,
+    ) -> Result<Self, DynError> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) =
+            (child.stdin.take(), child.stdout.take())
+        else {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err("Missing engine pipes".into());
+        };
+        let (tx, lines) = channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        // From here on, dropping the engine stops the process.
+        let mut engine = Engine {
+            child,
+            stdin,
+            lines,
+            seq: 0,
+        };
+        let name = engine
+            .send(&Request::Hello {
+                protocol: PROTOCOL_VERSION,
+            })
+            .and_then(|seq| engine.recv(seq, timeout))
+            .and_then(|line| Ok(serde_json::from_str::<Ready>(&line)?))
+            .map(|Ready::Ready { name }| name)
+            .map_err(|e| format!("Engine handshake failed: {e}"))?;
+        Ok(Self {
+            name,
+            timeout,
+            failures: AtomicUsize::new(0),
+            engine: Mutex::new(engine),
+        })
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    // Number of turns where the engine's reply was replaced by a fallback.
+    pub fn failures(&self) -> usize {
+        self.failures.load(Ordering::Relaxed)
+    }
+    fn request_action(
+        &self,
+        game: &GameState,
+        legal: &[TurnAction],
+        timeout: Duration,
+    ) -> Result<TurnAction, DynError> {
+        let mut view = game.clone();
+        view.redact_secrets(game.curr_player_idx);
+        let mut engine = self.engine.lock().map_err(|_| "Engine poisoned")?;
+        let seq = engine.send(&Request::Turn {
+            player: game.curr_player_idx,
+            game: &view,
+            legal_actions: legal,
+        })?;
+        let action: TurnAction =
+            serde_json::from_str(&engine.recv(seq, timeout)?)?;
+        game.clone().take_turn(&action)?;
+        Ok(action)
+    }
+    fn action_within(&self, game: &GameState, timeout: Duration) -> TurnAction {
+        let legal = legal_actions(game);
+        match self.request_action(game, &legal, timeout) {
+            Ok(action) => action,
+            Err(_) => {
+                self.failures.fetch_add(1, Ordering::Relaxed);
+                fallback_action(game)
+            }
+        }
+    }
+}
+
+impl Agent for ExternalAgent {
+    fn choose_action(&self, game: &GameState) -> TurnAction {
+        self.action_within(game, self.timeout)
+    }
+    // The engine gets until the deadline, or its own timeout if sooner.
+    fn choose_action_by(
+        &self,
+        game: &GameState,
+        deadline: Instant,
+    ) -> TurnAction {
+        let left = deadline.saturating_duration_since(Instant::now());
+        self.action_within(game, self.timeout.min(left))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    // Engine that always passes, which is illegal during template selection.
+    const PASSING_ENGINE: &str = r#"
+        read hello
+        echo '{"type": "ready", "seq": 1, "name": "passer"}'
+        while read request; do
+            seq=$(echo "$request" | sed 's/^{"seq":\([0-9]*\).*/\1/')
+            echo '{"seq": '$seq', "idx": {"DraftDie": [0, null]}, "coords": null, "tool": null}'
+        done
+    "#;
+
+    #[test]
+    fn test_external_agent() -> Result<(), DynError> {
+        let agent = ExternalAgent::spawn(
+            "sh",
+            &["-c", PASSING_ENGINE],
+            Duration::from_secs(5),
+        )?;
+        assert_eq!(agent.name(), "passer");
+        let mut game = GameState::init(2)?;
+        while !game.take_turn(&agent.choose_action(&game))? {}
+        // Only the two template selections needed a fallback.
+        assert_eq!(agent.failures(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_engine_timeout() -> Result<(), DynError> {
+        let script = r#"read hello; echo '{"type": "ready", "seq": 1, "name": "slow"}';
+            while read request; do sleep 5; done"#;
+        let agent = ExternalAgent::spawn(
+            "sh",
+            &["-c", script],
+            Duration::from_millis(50),
+        )?;
+        let game = GameState::init(2)?;
+        let action = agent.choose_action(&game);
+        assert!(game.clone().take_turn(&action).is_ok());
+        assert_eq!(agent.failures(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_late_reply() -> Result<(), DynError> {
+        // Answers the first turn too late, then the second one in time.
+        let script = r#"
+            read hello
+            echo '{"type": "ready", "seq": 1, "name": "late"}'
+            read request
+            sleep 0.5
+            echo '{"seq": 2, "idx": {"SelectTemplate": 1}}'
+            read request
+            echo '{"seq": 3, "idx": {"SelectTemplate": 0}}'
+            read quit
+        "#;
+        let agent = ExternalAgent::spawn(
+            "sh",
+            &["-c", script],
+            Duration::from_secs(5),
+        )?;
+        let game = GameState::init(2)?;
+        let deadline = Instant::now() + Duration::from_millis(50);
+        agent.choose_action_by(&game, deadline);
+        assert_eq!(agent.failures(), 1);
+        // The late reply to the first turn isn't taken for the second.
+        let action = agent.choose_action(&game);
+        assert!(
+            matches!(action.idx, crate::turn::ActionType::SelectTemplate(0)),
+            "{action:?}"
+        );
+        assert_eq!(agent.failures(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_handshake() {
+        let result = ExternalAgent::spawn(
+            "sh",
+            &["-c", "echo nope"],
+            Duration::from_secs(5),
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,457 @@
+use crate::agent::legal_actions;
+use crate::color::{Dice, PoolDie};
+use crate::config::GameConfig;
+use crate::constants::*;
+use crate::game::GameState;
+use crate::objective::ALL_OBJECTIVES;
+use crate::template::Slot;
+use crate::tool::{ALL_TOOL_TYPES, ToolData, ToolType};
+use crate::turn::{ActionType, TurnAction, TurnPhase};
+
+type DynError = Box<dyn std::error::Error>;
+
+const NUM_PHASES: usize = 4;
+// Per-game settings can vary, so encode up to the largest allowed values.
+const MAX_TOOLS: usize = ALL_TOOL_TYPES.len();
+const MAX_OBJECTIVES: usize = ALL_OBJECTIVES.len();
+// Tool types are one-hot encoded by their position in `ALL_TOOL_TYPES`.
+const NUM_TOOL_TYPES: usize = ALL_TOOL_TYPES.len();
+const MAX_POOL: usize = 2 * MAX_PLAYERS + 1;
+const NUM_CELLS: usize = BOARD_ROWS * BOARD_COLS;
+// Slot: color one-hot, face one-hot.
+const SLOT_FEATURES: usize = NUM_COLORS + 6;
+// Die: present flag, color one-hot, face one-hot (index 0 is a wildcard).
+const DIE_FEATURES: usize = 1 + NUM_COLORS + 7;
+const PLAYER_FEATURES: usize =
+    2 + NUM_CELLS * (SLOT_FEATURES + DIE_FEATURES) + NUM_TOOL_TYPES;
+const TEMPLATE_FEATURES: usize = 1 + NUM_CELLS * SLOT_FEATURES;
+const TOOL_FEATURES: usize = NUM_TOOL_TYPES + 1;
+
+// Size of the vector returned by `StainedEnv::observation`.
+pub const OBSERVATION_SIZE: usize = NUM_PHASES
+    + MAX_PLAYERS * PLAYER_FEATURES
+    + NUM_COLORS
+    + TEMPLATES_PER_PLAYER * TEMPLATE_FEATURES
+    + MAX_POOL * DIE_FEATURES
+    + MAX_ROUNDS * MAX_POOL * DIE_FEATURES
+    + MAX_TOOLS * TOOL_FEATURES
+    + MAX_OBJECTIVES * ALL_OBJECTIVES.len();
+
+// Layout of the flat action space:
+//   [SelectTemplate(i)] [pass, or return the wildcard as face 1-6]
+//   [DraftDie(idx, face) at cell] [UseTool(..) with payment]
+const PASS_ACTION: usize = TEMPLATES_PER_PLAYER;
+const DRAFT_OFFSET: usize = PASS_ACTION + 7;
+const DRAFT_ACTIONS: usize = MAX_POOL * 7 * NUM_CELLS;
+const TOOL_OFFSET: usize = DRAFT_OFFSET + DRAFT_ACTIONS;
+// Each tool slot gets a draft index plus one extra argument, which is
+// either a round track position or an increment flag.
+const TOOL_ARGS: usize = MAX_ROUNDS * MAX_POOL;
+// No payment, or the pool index of the die spent in a solo game.
+const PAYMENTS: usize = 1 + SOLO_POOL_SIZE;
+const TOOL_ACTIONS: usize = MAX_POOL * TOOL_ARGS * PAYMENTS;
+
+// Number of discrete actions; see `encode_action` for the layout.
+pub const NUM_ACTIONS: usize = TOOL_OFFSET + MAX_TOOLS * TOOL_ACTIONS;
+
+/// Result of a single `StainedEnv::step`.
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub obs: Vec<f32>,
+    pub reward: f32,
+    pub done: bool,
+}
+
+/// Gym-style environment: each step plays one action for the current player,
+/// and observations are always from the next player's point of view.
+pub struct StainedEnv {
+    num_players: usize,
+    config: GameConfig,
+    game: GameState,
+}
+impl StainedEnv {
+    pub fn new(num_players: usize) -> Result<Self, DynError> {
+        Self::with_config(num_players, GameConfig::default())
+    }
+    // Every reset uses this config, except for its seed.
+    pub fn with_config(
+        num_players: usize,
+        config: GameConfig,
+    ) -> Result<Self, DynError> {
+        Ok(Self {
+            num_players,
+            game: GameState::init_with_config(num_players, &config)?,
+            config,
+        })
+    }
+    pub fn game(&self) -> &GameState {
+        &self.game
+    }
+    pub fn reset(&mut self, seed: u64) -> Result<Vec<f32>, DynError> {
+        let config = GameConfig {
+            seed: Some(seed),
+            ..self.config.clone()
+        };
+        self.game = GameState::init_with_config(self.num_players, &config)?;
+        Ok(self.observation())
+    }
+    // Applies the action for the current player. The reward is the change
+    // in that player's score (as if the game ended now), so rewards sum to
+    // the final score minus the score of an empty board.
+    pub fn step(&mut self, action: usize) -> Result<Step, DynError> {
+        let action =
+            decode_action(&self.game, action).ok_or("Invalid action index")?;
+        let me = self.game.curr_player_idx;
+        let before = self.game.player_scores()[me].total();
+        let done = self.game.take_turn(&action)?;
+        let after = self.game.player_scores()[me].total();
+        Ok(Step {
+            obs: self.observation(),
+            reward: (after - before) as f32,
+            done,
+        })
+    }
+    pub fn legal_mask(&self) -> Vec<bool> {
+        let mut mask = vec![false; NUM_ACTIONS];
+        for idx in legal_action_indices(&self.game) {
+            mask[idx] = true;
+        }
+        mask
+    }
+    pub fn observation(&self) -> Vec<f32> {
+        observe(&self.game)
+    }
+}
+
+// Sorted indices of the current player's legal actions.
+pub fn legal_action_indices(game: &GameState) -> Vec<usize> {
+    let mut indices: Vec<usize> = legal_actions(game)
+        .iter()
+        .filter_map(|action| encode_action(game, action))
+        .collect();
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+}
+
+// Encodes the game as seen by the current player: other players' secrets
+// and the dice bag are never included.
+pub fn observe(game: &GameState) -> Vec<f32> {
+    let mut obs = Vec::with_capacity(OBSERVATION_SIZE);
+    one_hot(&mut obs, NUM_PHASES, Some(phase_idx(game.phase)));
+    let n = game.players.len();
+    for seat in 0..MAX_PLAYERS {
+        if seat >= n {
+            obs.extend(std::iter::repeat_n(0.0, PLAYER_FEATURES));
+            continue;
+        }
+        let player = &game.players[(game.curr_player_idx + seat) % n];
+        obs.push(1.0);
+        obs.push(player.tokens as f32);
+        for cell in player.board.iter().flatten() {
+            push_slot(&mut obs, cell.slot);
+            push_die(&mut obs, cell.die);
+        }
+        one_hot(
+            &mut obs,
+            NUM_TOOL_TYPES,
+            player.active_tool.and_then(tool_type_idx),
+        );
+    }
+    let me = game.current_player();
+    one_hot(&mut obs, NUM_COLORS, me.secret.map(|c| c as usize));
+    for i in 0..TEMPLATES_PER_PLAYER {
+        match me.templates.get(i) {
+            Some(template) => {
+                obs.push(template.value as f32);
+                for slot in template.slots.iter().flatten() {
+                    push_slot(&mut obs, *slot);
+                }
+            }
+            None => obs.extend(std::iter::repeat_n(0.0, TEMPLATE_FEATURES)),
+        }
+    }
+    for i in 0..MAX_POOL {
+        push_pool_die(&mut obs, game.draft_pool.get(i).copied());
+    }
+    for round in 0..MAX_ROUNDS {
+        push_dice(
+            &mut obs,
+            game.round_track.get(round).map_or(&[], |r| r.as_slice()),
+        );
+    }
+    for i in 0..MAX_TOOLS {
+        match game.tools.get(i) {
+            Some(tool) => {
+                one_hot(
+                    &mut obs,
+                    NUM_TOOL_TYPES,
+                    tool_type_idx(tool.tool_type),
+                );
+                obs.push(tool.cost as f32);
+            }
+            None => obs.extend(std::iter::repeat_n(0.0, TOOL_FEATURES)),
+        }
+    }
+    for i in 0..MAX_OBJECTIVES {
+        let idx = game.objectives.get(i).and_then(|obj| {
+            ALL_OBJECTIVES.iter().position(|o| {
+                std::mem::discriminant(o) == std::mem::discriminant(obj)
+            })
+        });
+        one_hot(&mut obs, ALL_OBJECTIVES.len(), idx);
+    }
+    debug_assert_eq!(obs.len(), OBSERVATION_SIZE);
+    obs
+}
+
+// Maps a `TurnAction` to its index in the flat action space, if it has one.
+pub fn encode_action(game: &GameState, action: &TurnAction) -> Option<usize> {
+    match (&action.idx, action.coords, &action.tool) {
+        (ActionType::SelectTemplate(i), _, _) if *i < TEMPLATES_PER_PLAYER => {
+            Some(*i)
+        }
+        (ActionType::DraftDie(_, None), None, _) => Some(PASS_ACTION),
+        (ActionType::DraftDie(idx, Some(face @ 1..=6)), None, _)
+            if game.pending_wild() == Some(*idx) =>
+        {
+            Some(PASS_ACTION + *face as usize)
+        }
+        (ActionType::DraftDie(idx, face), Some((r, c)), _) => {
+            let face = face.unwrap_or(0) as usize;
+            if *idx >= MAX_POOL
+                || face > 6
+                || r >= BOARD_ROWS
+                || c >= BOARD_COLS
+            {
+                return None;
+            }
+            Some(
+                DRAFT_OFFSET
+                    + (idx * 7 + face) * NUM_CELLS
+                    + r * BOARD_COLS
+                    + c,
+            )
+        }
+        (ActionType::UseTool(slot), _, Some(data)) => {
+            let tool = game.tools.get(*slot)?;
+            if !data.matches_type(tool.tool_type) {
+                return None;
+            }
+            let (draft_idx, arg) = match *data {
+                ToolData::BumpDraftedDie {
+                    draft_idx,
+                    is_increment,
+                } => (draft_idx, is_increment as usize),
+                ToolData::FlipDraftedDie { draft_idx }
+                | ToolData::RerollDraftedDie { draft_idx }
+                | ToolData::SwapDraftedDieWithBag { draft_idx } => {
+                    (draft_idx, 0)
+                }
+                ToolData::SwapDraftedDieWithRoundTrack {
+                    draft_idx,
+                    round_idx: (round, i),
+                    ..
+                } if round < MAX_ROUNDS && i < MAX_POOL => {
+                    (draft_idx, round * MAX_POOL + i)
+                }
+                ToolData::RerollAllDiceInPool
+                | ToolData::PlaceIgnoringAdjacency => (0, 0),
+                _ => return None,
+            };
+            let payment = match action.payment {
+                Some(i) if i < SOLO_POOL_SIZE => i + 1,
+                Some(_) => return None,
+                None => 0,
+            };
+            if draft_idx >= MAX_POOL {
+                return None;
+            }
+            Some(
+                TOOL_OFFSET
+                    + slot * TOOL_ACTIONS
+                    + (draft_idx * TOOL_ARGS + arg) * PAYMENTS
+                    + payment,
+            )
+        }
+        _ => None,
+    }
+}
+
+// Inverse of `encode_action`. Tool actions depend on which tools are in play.
+pub fn decode_action(game: &GameState, idx: usize) -> Option<TurnAction> {
+    if idx < PASS_ACTION {
+        return Some(TurnAction::new(ActionType::SelectTemplate(idx), None));
+    }
+    // Returning a wildcard to the pool also ends the turn without placing
+    // a die, so it follows the pass action.
+    if idx < DRAFT_OFFSET {
+        return match idx - PASS_ACTION {
+            0 => Some(TurnAction::pass()),
+            face => Some(TurnAction::new(
+                ActionType::DraftDie(game.pending_wild()?, Some(face as u8)),
+                None,
+            )),
+        };
+    }
+    if idx < TOOL_OFFSET {
+        let idx = idx - DRAFT_OFFSET;
+        let cell = idx % NUM_CELLS;
+        let face = (idx / NUM_CELLS) % 7;
+        let draft_idx = idx / NUM_CELLS / 7;
+        let face = if face == 0 { None } else { Some(face as u8) };
+        return Some(TurnAction::new(
+            ActionType::DraftDie(draft_idx, face),
+            Some((cell / BOARD_COLS, cell % BOARD_COLS)),
+        ));
+    }
+    if idx >= NUM_ACTIONS {
+        return None;
+    }
+    let idx = idx - TOOL_OFFSET;
+    let slot = idx / TOOL_ACTIONS;
+    let payment = (idx % PAYMENTS).checked_sub(1);
+    let idx = (idx % TOOL_ACTIONS) / PAYMENTS;
+    let draft_idx = idx / TOOL_ARGS;
+    let arg = idx % TOOL_ARGS;
+    let data = match (game.tools.get(slot)?.tool_type, arg) {
+        (ToolType::BumpDraftedDie, 0 | 1) => ToolData::BumpDraftedDie {
+            draft_idx,
+            is_increment: arg == 1,
+        },
+        (ToolType::FlipDraftedDie, 0) => ToolData::FlipDraftedDie { draft_idx },
+        (ToolType::RerollDraftedDie, 0) => {
+            ToolData::RerollDraftedDie { draft_idx }
+        }
+        (ToolType::SwapDraftedDieWithBag, 0) => {
+            ToolData::SwapDraftedDieWithBag { draft_idx }
+        }
+        (ToolType::SwapDraftedDieWithRoundTrack, _) => {
+            ToolData::SwapDraftedDieWithRoundTrack {
+                draft_idx,
+                round_idx: (arg / MAX_POOL, arg % MAX_POOL),
+                round_die_id: None,
+            }
+        }
+        (ToolType::RerollAllDiceInPool, 0) if draft_idx == 0 => {
+            ToolData::RerollAllDiceInPool
+        }
+        (ToolType::PlaceIgnoringAdjacency, 0) if draft_idx == 0 => {
+            ToolData::PlaceIgnoringAdjacency
+        }
+        _ => return None,
+    };
+    Some(TurnAction {
+        payment,
+        ..TurnAction::use_tool(slot, data)
+    })
+}
+
+fn tool_type_idx(tool_type: ToolType) -> Option<usize> {
+    ALL_TOOL_TYPES.iter().position(|&t| t == tool_type)
+}
+
+fn phase_idx(phase: TurnPhase) -> usize {
+    match phase {
+        TurnPhase::SelectTemplate => 0,
+        TurnPhase::FirstDraft => 1,
+        TurnPhase::SecondDraft => 2,
+        TurnPhase::GameOver => 3,
+    }
+}
+
+fn one_hot(obs: &mut Vec<f32>, size: usize, idx: Option<usize>) {
+    obs.extend((0..size).map(|i| if Some(i) == idx { 1.0 } else { 0.0 }));
+}
+
+fn push_slot(obs: &mut Vec<f32>, slot: Slot) {
+    let (color, face) = match slot {
+        Slot::Any => (None, None),
+        Slot::Color(color) => (Some(color as usize), None),
+        Slot::Face(face) => (None, Some(face as usize - 1)),
+    };
+    one_hot(obs, NUM_COLORS, color);
+    one_hot(obs, 6, face);
+}
+
+fn push_die(obs: &mut Vec<f32>, die: Option<Dice>) {
+    obs.push(if die.is_some() { 1.0 } else { 0.0 });
+    one_hot(obs, NUM_COLORS, die.map(|d| d.color as usize));
+    one_hot(obs, 7, die.map(|d| d.face as usize));
+}
+
+fn push_pool_die(obs: &mut Vec<f32>, die: Option<PoolDie>) {
+    match die {
+        Some(PoolDie::Wild(color, _)) => {
+            obs.push(1.0);
+            one_hot(obs, NUM_COLORS, Some(color as usize));
+            one_hot(obs, 7, Some(0));
+        }
+        die => push_die(obs, die.and_then(PoolDie::rolled)),
+    }
+}
+
+fn push_dice(obs: &mut Vec<f32>, dice: &[Dice]) {
+    for i in 0..MAX_POOL {
+        push_die(obs, dice.get(i).copied());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_encoding_roundtrip() -> Result<(), DynError> {
+        let agent = crate::agent::create_agent(0);
+        // Solo games also choose which die pays for a tool.
+        for num_players in [1, 4] {
+            let mut game = GameState::init_with_seed(num_players, 7)?;
+            while !matches!(game.phase, TurnPhase::GameOver) {
+                for action in legal_actions(&game) {
+                    let idx = encode_action(&game, &action).expect("encodable");
+                    let decoded = decode_action(&game, idx).expect("decodable");
+                    assert_eq!(Some(idx), encode_action(&game, &decoded));
+                    assert_eq!(
+                        format!("{:?}", decoded.idx),
+                        format!("{:?}", action.idx)
+                    );
+                    assert_eq!(decoded.payment, action.payment);
+                    assert!(
+                        game.clone().take_turn(&decoded).is_ok(),
+                        "{action:?}"
+                    );
+                }
+                game.take_turn(&agent.choose_action(&game))?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_env_episode() -> Result<(), DynError> {
+        let mut env = StainedEnv::new(2)?;
+        let obs = env.reset(3)?;
+        assert_eq!(obs.len(), OBSERVATION_SIZE);
+        let mut total_rewards = [0.0; 2];
+        for turn in 0.. {
+            let me = env.game().curr_player_idx;
+            let mask = env.legal_mask();
+            let legal: Vec<usize> =
+                (0..NUM_ACTIONS).filter(|&i| mask[i]).collect();
+            let step = env.step(legal[turn * 7 % legal.len()])?;
+            assert_eq!(step.obs.len(), OBSERVATION_SIZE);
+            total_rewards[me] += step.reward;
+            if step.done {
+                break;
+            }
+        }
+        let empty_board = -((BOARD_ROWS * BOARD_COLS) as f32);
+        for (score, reward) in
+            env.game().player_scores().iter().zip(total_rewards)
+        {
+            assert_eq!(score.total() as f32, empty_board + reward);
+        }
+        Ok(())
+    }
+}
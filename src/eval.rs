@@ -0,0 +1,354 @@
+use crate::board::{Board, BoardCell};
+use crate::color::{ALL_COLORS, Color, Dice};
+use crate::constants::{BOARD_COLS, BOARD_ROWS, NUM_COLORS};
+use crate::game::{GameState, Player, diagonal_coords, neighbor_coords};
+use crate::objective::Objective;
+use crate::template::Slot;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+type DynError = Box<dyn std::error::Error>;
+type Fill = [[f64; BOARD_COLS]; BOARD_ROWS];
+
+/// Weights applied to each component of a `BoardEstimate`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EvalWeights {
+    pub objectives: f64,
+    pub secret_color: f64,
+    pub unused_tokens: f64,
+    pub empty_slots: f64,
+    // Extra penalty per cell that can never be filled.
+    pub unfillable: f64,
+}
+impl Default for EvalWeights {
+    fn default() -> Self {
+        Self {
+            objectives: 1.0,
+            secret_color: 1.0,
+            unused_tokens: 1.0,
+            empty_slots: 1.0,
+            unfillable: 0.0,
+        }
+    }
+}
+impl EvalWeights {
+    pub fn from_json(json: &str) -> Result<Self, DynError> {
+        Ok(serde_json::from_str(json)?)
+    }
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DynError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
+
+/// Expected final score components for a partially filled board.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BoardEstimate {
+    pub objectives: Vec<f64>,
+    pub secret_color: f64,
+    pub unused_tokens: f64,
+    pub empty_slots: f64,
+    pub unfillable: usize,
+}
+impl BoardEstimate {
+    pub fn score(&self, weights: &EvalWeights) -> f64 {
+        weights.objectives * self.objectives.iter().sum::<f64>()
+            + weights.secret_color * self.secret_color
+            + weights.unused_tokens * self.unused_tokens
+            + weights.empty_slots * self.empty_slots
+            + weights.unfillable * self.unfillable as f64
+    }
+}
+
+// Weighted estimate of the final score for one player in the given game.
+pub fn evaluate(
+    game: &GameState,
+    player_idx: usize,
+    weights: &EvalWeights,
+) -> f64 {
+    estimate(
+        &game.players[player_idx],
+        &game.objectives,
+        game.drafts_remaining(player_idx),
+        game.empty_slot_penalty(),
+    )
+    .score(weights)
+}
+
+// Estimates the final score of a board, assuming the player will get to
+// draft `drafts_left` more dice, and loses `empty_slot_penalty` points for
+// each slot left empty.
+pub fn estimate(
+    player: &Player,
+    objectives: &[Objective],
+    drafts_left: usize,
+    empty_slot_penalty: i32,
+) -> BoardEstimate {
+    let fill = fill_probabilities(&player.board, drafts_left);
+    let objectives = objectives
+        .iter()
+        .map(|obj| objective_estimate(*obj, &player.board, &fill))
+        .collect();
+    let mut secret_color = 0.0;
+    let mut empty_slots = 0.0;
+    for (i, fill_row) in fill.iter().enumerate() {
+        for (j, &p) in fill_row.iter().enumerate() {
+            match player.board[i][j].die {
+                Some(die) if Some(die.color) == player.secret => {
+                    secret_color += die.face as f64;
+                }
+                Some(_) => {}
+                None => {
+                    secret_color += player.secret.map_or(0.0, |secret| {
+                        p * expected_pips(&player.board, (i, j), secret)
+                    });
+                    empty_slots -= (1.0 - p) * empty_slot_penalty as f64;
+                }
+            }
+        }
+    }
+    BoardEstimate {
+        objectives,
+        secret_color,
+        unused_tokens: player.tokens as f64,
+        empty_slots,
+        unfillable: unfillable_cells(&player.board),
+    }
+}
+
+// Counts empty cells where no die can ever be placed, given the slot
+// constraint and the dice already placed orthogonally next to it.
+pub fn unfillable_cells(board: &Board) -> usize {
+    (0..BOARD_ROWS)
+        .flat_map(|i| (0..BOARD_COLS).map(move |j| (i, j)))
+        .filter(|&(i, j)| {
+            board[i][j].die.is_none() && !is_fillable(board, (i, j))
+        })
+        .count()
+}
+
+fn is_fillable(board: &Board, coords: (usize, usize)) -> bool {
+    ALL_COLORS.iter().any(|&color| {
+        (1..=6).any(|face| allows(board, coords, Dice::new(color, face)))
+    })
+}
+
+// Like `Player::can_place_die`, but ignores the adjacency requirement
+// because more dice may be placed nearby later.
+fn allows(board: &Board, coords: (usize, usize), die: Dice) -> bool {
+    board[coords.0][coords.1].slot.accepts(die)
+        && neighbor_coords(coords).all(|(r, c)| match board[r][c].die {
+            Some(nbr) => nbr.color != die.color && nbr.face != die.face,
+            None => true,
+        })
+}
+
+// Probability that each empty cell will have a die by the end of the game.
+fn fill_probabilities(board: &Board, drafts_left: usize) -> Fill {
+    let mut fill = [[0.0; BOARD_COLS]; BOARD_ROWS];
+    let mut open = Vec::new();
+    for i in 0..BOARD_ROWS {
+        for j in 0..BOARD_COLS {
+            if board[i][j].die.is_some() {
+                fill[i][j] = 1.0;
+            } else if is_fillable(board, (i, j)) {
+                open.push((i, j));
+            }
+        }
+    }
+    if !open.is_empty() {
+        let p = (drafts_left as f64 / open.len() as f64).min(1.0);
+        for (i, j) in open {
+            fill[i][j] = p;
+        }
+    }
+    fill
+}
+
+// Expected pips of the secret color that an empty cell will contribute,
+// given that it gets filled.
+fn expected_pips(board: &Board, coords: (usize, usize), secret: Color) -> f64 {
+    if neighbor_coords(coords)
+        .any(|(r, c)| matches!(board[r][c].die, Some(d) if d.color == secret))
+    {
+        return 0.0;
+    }
+    let p_color = 1.0 / NUM_COLORS as f64;
+    match board[coords.0][coords.1].slot {
+        Slot::Color(color) if color == secret => 3.5,
+        Slot::Color(_) => 0.0,
+        Slot::Face(face) => p_color * face as f64,
+        Slot::Any => p_color * 3.5,
+    }
+}
+
+// Expected score of a single objective, using the per-cell fill estimates.
+fn objective_estimate(obj: Objective, board: &Board, fill: &Fill) -> f64 {
+    let rows: Vec<Vec<_>> = (0..BOARD_ROWS)
+        .map(|i| (0..BOARD_COLS).map(|j| (i, j)).collect())
+        .collect();
+    let cols: Vec<Vec<_>> = (0..BOARD_COLS)
+        .map(|j| (0..BOARD_ROWS).map(|i| (i, j)).collect())
+        .collect();
+    let distinct = |lines: &[Vec<(usize, usize)>], key: Key| -> f64 {
+        lines
+            .iter()
+            .map(|line| distinct_probability(board, fill, line, key))
+            .sum()
+    };
+    obj.points() as f64
+        * match obj {
+            Objective::ColumnNumbers(_) => distinct(&cols, FACE),
+            Objective::RowNumbers(_) => distinct(&rows, FACE),
+            Objective::Numbers(_) => expected_sets(board, fill, FACE, 0..6),
+            Objective::ColumnColors(_) => distinct(&cols, COLOR),
+            Objective::RowColors(_) => distinct(&rows, COLOR),
+            Objective::Colors(_) => {
+                expected_sets(board, fill, COLOR, 0..NUM_COLORS)
+            }
+            Objective::Pair12(_) => expected_sets(board, fill, FACE, 0..2),
+            Objective::Pair34(_) => expected_sets(board, fill, FACE, 2..4),
+            Objective::Pair56(_) => expected_sets(board, fill, FACE, 4..6),
+            Objective::ColorDiagonals(_) => expected_diagonals(board, fill),
+        }
+}
+
+// Maps a cell to the index of its known (or slot-forced) face or color.
+#[derive(Clone, Copy)]
+struct Key {
+    size: usize,
+    value: fn(&BoardCell) -> Option<usize>,
+}
+const FACE: Key = Key {
+    size: 6,
+    value: |cell| match (cell.die, cell.slot) {
+        (Some(die), _) => Some(die.face as usize - 1),
+        (None, Slot::Face(face)) => Some(face as usize - 1),
+        _ => None,
+    },
+};
+const COLOR: Key = Key {
+    size: NUM_COLORS,
+    value: |cell| match (cell.die, cell.slot) {
+        (Some(die), _) => Some(die.color as usize),
+        (None, Slot::Color(color)) => Some(color as usize),
+        _ => None,
+    },
+};
+
+// Probability that every cell in the line ends up filled with distinct
+// values, treating unconstrained cells as uniformly random.
+fn distinct_probability(
+    board: &Board,
+    fill: &Fill,
+    line: &[(usize, usize)],
+    key: Key,
+) -> f64 {
+    let mut seen = vec![false; key.size];
+    let mut free = 0;
+    let mut prob = 1.0;
+    for &(i, j) in line {
+        match (key.value)(&board[i][j]) {
+            Some(v) if seen[v] => return 0.0,
+            Some(v) => seen[v] = true,
+            None => free += 1,
+        }
+        prob *= fill[i][j];
+    }
+    let mut remaining = seen.iter().filter(|s| !**s).count();
+    for _ in 0..free {
+        prob *= remaining as f64 / key.size as f64;
+        remaining = remaining.saturating_sub(1);
+    }
+    prob
+}
+
+// Expected number of complete sets of the given values on the final board.
+fn expected_sets(
+    board: &Board,
+    fill: &Fill,
+    key: Key,
+    values: std::ops::Range<usize>,
+) -> f64 {
+    let mut counts = vec![0.0; key.size];
+    for (row, fill_row) in board.iter().zip(fill) {
+        for (cell, p) in row.iter().zip(fill_row) {
+            match (key.value)(cell) {
+                Some(v) => counts[v] += p,
+                None => {
+                    counts.iter_mut().for_each(|c| *c += p / key.size as f64)
+                }
+            }
+        }
+    }
+    counts[values].iter().copied().fold(f64::INFINITY, f64::min)
+}
+
+// Expected number of dice sharing a color with a diagonal neighbor.
+fn expected_diagonals(board: &Board, fill: &Fill) -> f64 {
+    let p_match = 1.0 / NUM_COLORS as f64;
+    let mut total = 0.0;
+    for i in 0..BOARD_ROWS {
+        for j in 0..BOARD_COLS {
+            let color = board[i][j].die.map(|d| d.color);
+            let mut p_none = 1.0;
+            for (r, c) in diagonal_coords((i, j)) {
+                p_none *= match (color, board[r][c].die) {
+                    (Some(a), Some(b)) if a == b.color => 0.0,
+                    (_, Some(_)) if color.is_some() => 1.0,
+                    _ => 1.0 - fill[r][c] * p_match,
+                };
+            }
+            total += fill[i][j] * (1.0 - p_none);
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weights_from_json() {
+        let weights =
+            EvalWeights::from_json(r#"{"unfillable": -2.5}"#).unwrap();
+        assert_eq!(weights.unfillable, -2.5);
+        assert_eq!(weights.objectives, 1.0);
+        assert!(EvalWeights::from_json(r#"{"objectives": "x"}"#).is_err());
+    }
+
+    #[test]
+    fn test_unfillable_cells() {
+        let mut board = [[BoardCell::default(); BOARD_COLS]; BOARD_ROWS];
+        assert_eq!(unfillable_cells(&board), 0);
+        // A red slot surrounded by red dice can never be filled.
+        board[1][1].slot = Slot::Color(Color::Red);
+        for (r, c) in neighbor_coords((1, 1)) {
+            board[r][c].die = Some(Dice::new(Color::Red, (r + c) as u8));
+        }
+        assert_eq!(unfillable_cells(&board), 1);
+    }
+
+    #[test]
+    fn test_estimate_finished_board() -> Result<(), DynError> {
+        let config = crate::config::GameConfig {
+            seed: Some(3),
+            rules: crate::config::Rules {
+                empty_slot_penalty: 4,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut game = GameState::init_with_config(2, &config)?;
+        let agent = crate::agent::create_agent(1);
+        while !game.take_turn(&agent.choose_action(&game))? {}
+        // With no drafts left the estimate matches the actual score.
+        for (player, score) in game.players.iter().zip(game.player_scores()) {
+            let est = estimate(player, &game.objectives, 0, 4);
+            let total = est.score(&EvalWeights::default());
+            assert!((total - score.total() as f64).abs() < 1e-9, "{est:?}");
+        }
+        Ok(())
+    }
+}
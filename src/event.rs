@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+use crate::color::{Dice, PoolDie};
+use crate::constants::{BOARD_COLS, BOARD_ROWS};
+use crate::game::ScoreBreakdown;
+use crate::template::{Slot, TemplateInfo};
+use crate::tool::ToolData;
+use crate::turn::TurnPhase;
+
+/// Something that happened in the game, in enough detail for a client to
+/// update its copy of the game without a full view.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GameEvent {
+    TemplateSelected {
+        player: usize,
+        template: ChosenTemplate,
+    },
+    // A template picked during simultaneous selection. Only the player who
+    // picked it can see which one; everyone gets `TemplateSelected` once
+    // all players have picked.
+    TemplatePicked {
+        player: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        template: Option<ChosenTemplate>,
+    },
+    DiceRolled {
+        dice: Vec<PoolDie>,
+    },
+    TurnStarted {
+        player: usize,
+        phase: TurnPhase,
+    },
+    DieDrafted {
+        player: usize,
+        die: Dice,
+        coords: (usize, usize),
+    },
+    // A wildcard die that couldn't be placed went back to the pool.
+    DieReturned {
+        player: usize,
+        die: Dice,
+    },
+    Passed {
+        player: usize,
+    },
+    ToolUsed {
+        player: usize,
+        tool: usize,
+        data: ToolData,
+        // Tokens paid, or the die spent on the tool in solo games.
+        tokens: u8,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        payment: Option<PoolDie>,
+        // The draft pool after the tool's effect.
+        draft_pool: Vec<PoolDie>,
+        // The round track die swapped in, if the tool changed one.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        round_track_die: Option<((usize, usize), Dice)>,
+    },
+    // A seat was handed to an AI of the given level, or back to a human.
+    SeatChanged {
+        player: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ai_level: Option<u8>,
+    },
+    RoundEnded {
+        round: usize,
+        leftover: Vec<Dice>,
+    },
+    GameEnded {
+        scores: Vec<ScoreBreakdown>,
+        // Player indices from best to worst score.
+        standings: Vec<usize>,
+        winner: Option<usize>,
+    },
+}
+
+/// The parts of a player's template that others can see.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChosenTemplate {
+    #[serde(flatten)]
+    pub info: TemplateInfo,
+    pub slots: [[Slot; BOARD_COLS]; BOARD_ROWS],
+    pub tokens: u8,
+}
+
+impl GameEvent {
+    // The event as the given player, or a spectator if None, may see it.
+    pub fn redacted(&self, viewer: Option<usize>) -> Self {
+        match self {
+            GameEvent::TemplatePicked { player, .. }
+                if Some(*player) != viewer =>
+            {
+                GameEvent::TemplatePicked {
+                    player: *player,
+                    template: None,
+                }
+            }
+            event => event.clone(),
+        }
+    }
+    // What the player did, e.g. "placed Y4 at (2, 3)", if this event is a
+    // player's action rather than one of its consequences.
+    pub fn describe(&self) -> Option<String> {
+        Some(match self {
+            GameEvent::TemplateSelected { template, .. } => {
+                format!("selected {}", template.info.name)
+            }
+            GameEvent::TemplatePicked { .. } => "picked a template".into(),
+            GameEvent::DieDrafted { die, coords, .. } => {
+                format!("placed {die} at ({}, {})", coords.0, coords.1)
+            }
+            GameEvent::DieReturned { die, .. } => {
+                format!("returned {die} to the draft pool")
+            }
+            GameEvent::Passed { .. } => "passed".into(),
+            GameEvent::ToolUsed { data, .. } => {
+                format!("used {:?}", data.tool_type())
+            }
+            _ => return None,
+        })
+    }
+}
@@ -1,70 +1,220 @@
-use crate::board::BoardCell;
-use crate::color::{ALL_COLORS, Color, Dice};
+use crate::board::{Board, BoardCell, parse_grid, split_headers};
+use crate::color::{ALL_COLORS, Color, Dice, DieId, PoolDie, die_color};
+use crate::config::{GameConfig, Rules};
 use crate::constants::*;
+use crate::event::{ChosenTemplate, GameEvent};
 use crate::objective::{ALL_OBJECTIVES, Objective};
-use crate::template::{ALL_BOARD_TEMPLATES, BoardTemplate, Slot};
+use crate::template::{BoardTemplate, Slot, TemplateInfo};
 use crate::tool::{ALL_TOOL_TYPES, Tool, ToolData, ToolType};
 use crate::turn::{ActionType, TurnAction, TurnPhase};
+use rand::SeedableRng;
 use rand::prelude::SliceRandom;
+use rand::rngs::StdRng;
 use rand::seq::{IndexedRandom, IteratorRandom};
 use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::str::FromStr;
 
 type DynError = Box<dyn std::error::Error>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RawGameState")]
 pub struct GameState {
     pub players: Vec<Player>,
     start_player_idx: usize,
     pub curr_player_idx: usize,
     pub phase: TurnPhase,
     #[serde(skip)]
-    dice_bag: Vec<Color>,
-    pub draft_pool: Vec<Dice>,
+    dice_bag: Vec<DieId>,
+    pub draft_pool: Vec<PoolDie>,
     pub round_track: Vec<Vec<Dice>>,
     pub tools: Vec<Tool>,
     pub objectives: Vec<Objective>,
+    #[serde(default)]
+    pub rules: Rules,
+    #[serde(skip)]
+    rng: StdRng,
+    // What happened during the most recent action.
+    #[serde(skip)]
+    events: Vec<GameEvent>,
+}
+
+// Serialized form of `GameState`. The bag is refilled with every die not in
+// play, then the whole game is validated.
+#[derive(Deserialize)]
+struct RawGameState {
+    players: Vec<Player>,
+    start_player_idx: usize,
+    curr_player_idx: usize,
+    phase: TurnPhase,
+    draft_pool: Vec<PoolDie>,
+    // Wildcards nobody placed before the round ended are rolled.
+    round_track: Vec<Vec<PoolDie>>,
+    tools: Vec<RawTool>,
+    objectives: Vec<Objective>,
+    #[serde(default)]
+    rules: Rules,
+}
+// Tools saved before they held their tokens only have a cost. One that
+// costs more than the base price has been paid for at least once.
+#[derive(Deserialize)]
+struct RawTool {
+    tool_type: ToolType,
+    cost: u8,
+    tokens: Option<u8>,
+    #[serde(default)]
+    used: bool,
+}
+impl TryFrom<RawGameState> for GameState {
+    type Error = String;
+    fn try_from(raw: RawGameState) -> Result<Self, Self::Error> {
+        let mut rng = StdRng::from_os_rng();
+        let round_track = raw
+            .round_track
+            .into_iter()
+            .map(|round| {
+                round
+                    .into_iter()
+                    .map(|d| d.roll_if_wild(&mut rng))
+                    .collect()
+            })
+            .collect();
+        let mut game = GameState {
+            players: raw.players,
+            start_player_idx: raw.start_player_idx,
+            curr_player_idx: raw.curr_player_idx,
+            phase: raw.phase,
+            dice_bag: Vec::new(),
+            draft_pool: raw.draft_pool,
+            round_track,
+            tools: raw
+                .tools
+                .into_iter()
+                .map(|tool| Tool {
+                    tool_type: tool.tool_type,
+                    cost: tool.cost,
+                    tokens: tool.tokens.unwrap_or(
+                        if tool.cost > raw.rules.tool_cost {
+                            raw.rules.tool_cost
+                        } else {
+                            0
+                        },
+                    ),
+                    used: tool.used,
+                })
+                .collect(),
+            objectives: raw.objectives,
+            rules: raw.rules,
+            rng,
+            events: Vec::new(),
+        };
+        game.refill_bag().map_err(|e| e.to_string())?;
+        game.validate().map_err(|e| e.to_string())?;
+        Ok(game)
+    }
 }
 impl GameState {
     pub fn init(num_players: usize) -> Result<Self, DynError> {
-        if !(2..=MAX_PLAYERS).contains(&num_players) {
+        Self::init_with_config(num_players, &GameConfig::default())
+    }
+    // Deterministic setup: the same seed always produces the same game.
+    pub fn init_with_seed(
+        num_players: usize,
+        seed: u64,
+    ) -> Result<Self, DynError> {
+        let config = GameConfig {
+            seed: Some(seed),
+            ..Default::default()
+        };
+        Self::init_with_config(num_players, &config)
+    }
+    pub fn init_with_config(
+        num_players: usize,
+        config: &GameConfig,
+    ) -> Result<Self, DynError> {
+        if !(1..=MAX_PLAYERS).contains(&num_players) {
             return Err("Invalid number of players".into());
         }
-        let mut dice_bag = Vec::with_capacity(DICE_PER_COLOR * NUM_COLORS);
-        for _ in 0..DICE_PER_COLOR {
-            dice_bag.extend_from_slice(ALL_COLORS.as_slice());
-        }
-        let mut rng = rand::rng();
+        config.validate(num_players)?;
+        let mut rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_os_rng(),
+        };
+        let rules = &config.rules;
+        let dice_per_color = dice_per_color(num_players, rules.num_rounds);
+        let mut dice_bag: Vec<DieId> =
+            (0..(dice_per_color * NUM_COLORS) as DieId).collect();
         dice_bag.shuffle(&mut rng);
 
         let start_player_idx = (0..num_players).choose(&mut rng).unwrap_or(0);
-        let player_templates: Vec<_> = ALL_BOARD_TEMPLATES
-            .choose_multiple(&mut rng, num_players * 2)
-            .collect();
-        if player_templates.len() != num_players * 2 {
+        let all_cards = config.template_cards()?;
+        let player_templates: Vec<_> = match &config.templates {
+            Some(cards) => cards
+                .iter()
+                .flatten()
+                .map(|&card| &all_cards[card])
+                .collect(),
+            None => all_cards
+                .choose_multiple(&mut rng, num_players * CARDS_PER_PLAYER)
+                .collect(),
+        };
+        if player_templates.len() != num_players * CARDS_PER_PLAYER {
             return Err("Not enough templates for this many players".into());
         }
-        let players = ALL_COLORS
-            .choose_multiple(&mut rng, num_players)
-            .zip(player_templates.as_slice().chunks(2))
+        // With more players than colors, some secret colors are dealt twice.
+        let secrets: Vec<Color> = ALL_COLORS
+            .iter()
+            .cycle()
+            .take(num_players.div_ceil(NUM_COLORS) * NUM_COLORS)
+            .copied()
+            .collect();
+        // A solo player gets two secret colors and scores the better one.
+        let solo = num_players == 1;
+        let mut dealt: Vec<Color> = secrets
+            .choose_multiple(&mut rng, num_players + solo as usize)
+            .copied()
+            .collect();
+        let second_secret = if solo { dealt.pop() } else { None };
+        let players = dealt
+            .into_iter()
+            .zip(player_templates.as_slice().chunks(CARDS_PER_PLAYER))
             .map(|(secret, templates)| Player {
                 tokens: 0,
                 board: [[BoardCell::default(); BOARD_COLS]; BOARD_ROWS],
-                secret: *secret,
+                secret: Some(secret),
+                second_secret,
                 templates: templates
                     .iter()
                     .flat_map(|x| x.iter().cloned())
                     .collect(),
+                template: None,
                 active_tool: None,
             })
             .collect();
 
-        let tools = ALL_TOOL_TYPES
-            .choose_multiple(&mut rng, NUM_TOOLS)
+        let tool_types: Vec<ToolType> = match &config.tools {
+            Some(tools) => tools.clone(),
+            None => ALL_TOOL_TYPES
+                .choose_multiple(&mut rng, rules.num_tools)
+                .copied()
+                .collect(),
+        };
+        let tools = tool_types
+            .into_iter()
             .map(|tool_type| Tool {
-                tool_type: *tool_type,
-                cost: 1,
+                tool_type,
+                cost: rules.tool_cost,
+                tokens: 0,
+                used: false,
             })
             .collect();
+        let objectives = match &config.objectives {
+            Some(objectives) => objectives.clone(),
+            None => ALL_OBJECTIVES
+                .choose_multiple(&mut rng, rules.num_objectives(num_players))
+                .copied()
+                .collect(),
+        };
 
         Ok(Self {
             players,
@@ -75,14 +225,271 @@ impl GameState {
             draft_pool: Vec::new(),
             round_track: Vec::new(),
             tools,
-            objectives: ALL_OBJECTIVES
-                .choose_multiple(&mut rng, NUM_OBJECTIVES)
-                .copied()
-                .collect(),
+            objectives,
+            rules: rules.clone(),
+            rng,
+            events: Vec::new(),
         })
     }
+    pub fn builder(num_players: usize) -> GameStateBuilder {
+        GameStateBuilder::new(num_players)
+    }
+    // Checks that the game could have been reached by legal play.
+    pub fn validate(&self) -> Result<(), DynError> {
+        self.check()
+            .map_err(|e| format!("Invalid game state: {e}").into())
+    }
+    fn check(&self) -> Result<(), String> {
+        let n = self.players.len();
+        if !(1..=MAX_PLAYERS).contains(&n) {
+            return Err(format!("must have 1 to {MAX_PLAYERS} players"));
+        }
+        if self.start_player_idx >= n || self.curr_player_idx >= n {
+            return Err("player index out of range".into());
+        }
+        // Phase and round progress.
+        let rounds = self.round_track.len();
+        let placed: Vec<usize> = self
+            .players
+            .iter()
+            .map(|p| {
+                p.board.iter().flatten().filter(|c| c.die.is_some()).count()
+            })
+            .collect();
+        let max_placed = match self.phase {
+            TurnPhase::SelectTemplate => {
+                if rounds > 0 || !self.draft_pool.is_empty() {
+                    return Err(
+                        "dice rolled before templates were selected".into()
+                    );
+                }
+                if self.current_player().templates.is_empty() {
+                    return Err(
+                        "current player has no templates to select".into()
+                    );
+                }
+                0
+            }
+            TurnPhase::GameOver
+                if rounds != self.rules.num_rounds
+                    || !self.draft_pool.is_empty() =>
+            {
+                return Err("game over before the last round".into());
+            }
+            TurnPhase::GameOver => 2 * rounds,
+            _ if rounds >= self.rules.num_rounds => {
+                return Err(format!("round track has {rounds} rounds"));
+            }
+            _ => 2 * (rounds + 1),
+        };
+        if self.phase != TurnPhase::SelectTemplate
+            && self.players.iter().any(|p| !p.templates.is_empty())
+        {
+            return Err("templates left unselected".into());
+        }
+        if let Some(i) = placed.iter().position(|&count| count > max_placed) {
+            return Err(format!("player {i} has placed too many dice"));
+        }
+        let max_pool = if self.is_solo() {
+            SOLO_POOL_SIZE
+        } else {
+            2 * n + 1
+        };
+        if self.draft_pool.len() > max_pool {
+            return Err(format!("draft pool has more than {max_pool} dice"));
+        }
+        let dice = self
+            .draft_pool
+            .iter()
+            .filter_map(|d| d.rolled())
+            .chain(self.round_track.iter().flatten().copied());
+        if let Some(die) = dice.clone().find(|d| !(1..=6).contains(&d.face)) {
+            return Err(format!("invalid die {die:?}"));
+        }
+        // A wildcard only exists until the player who drew it from the bag
+        // places it or returns it to the pool.
+        let wilds = self.draft_pool.iter().filter(|d| d.is_wild()).count();
+        if wilds > 1
+            || wilds == 1
+                && self.current_player().active_tool
+                    != Some(ToolType::SwapDraftedDieWithBag)
+        {
+            return Err("wildcard die left in the draft pool".into());
+        }
+        for (i, player) in self.players.iter().enumerate() {
+            player
+                .check_board()
+                .map_err(|e| format!("player {i}: {e}"))?;
+            if player.secret.is_none() {
+                return Err(format!("player {i} has no secret color"));
+            }
+            if player.active_tool.is_some() && i != self.curr_player_idx {
+                return Err(format!("player {i} has a tool active"));
+            }
+            if self.is_solo() && player.tokens > 0 {
+                return Err("solo players have no tokens".into());
+            }
+        }
+        // Dice are conserved.
+        let per_color =
+            dice_per_color(self.players.len(), self.rules.num_rounds);
+        let in_play = self.dice_in_play();
+        for (color, count) in ALL_COLORS.iter().zip(in_play) {
+            let in_bag = self
+                .dice_bag
+                .iter()
+                .filter(|&&id| die_color(id) == *color)
+                .count();
+            if count + in_bag != per_color {
+                return Err(format!(
+                    "{count} {color:?} dice in play and {in_bag} in the bag"
+                ));
+            }
+        }
+        // Each die keeps its own id, which fixes its color.
+        let total = per_color * NUM_COLORS;
+        let mut seen = vec![false; total];
+        let bag = self.dice_bag.iter().map(|&id| (die_color(id), Some(id)));
+        for (color, id) in self.loose_dice().chain(bag) {
+            let id = id.ok_or(format!("{color:?} die has no id"))?;
+            if id as usize >= total || die_color(id) != color {
+                return Err(format!("{color:?} die has id {id}"));
+            }
+            if std::mem::replace(&mut seen[id as usize], true) {
+                return Err(format!("duplicate die id {id}"));
+            }
+        }
+        // Tools hold the tokens paid for them.
+        let base = self.rules.tool_cost;
+        for (i, tool) in self.tools.iter().enumerate() {
+            let name = tool.tool_type;
+            if self.tools[..i].iter().any(|t| t.tool_type == name) {
+                return Err(format!("duplicate tool {name:?}"));
+            }
+            let consistent = if self.is_solo() {
+                tool.cost == base && tool.tokens == 0
+            } else if tool.used {
+                false
+            } else if tool.cost == base {
+                tool.tokens == 0
+            } else {
+                let double = base.saturating_mul(2);
+                tool.cost == double
+                    && tool.tokens >= base
+                    && (tool.tokens - base).is_multiple_of(double)
+            };
+            if !consistent {
+                return Err(format!(
+                    "{name:?} costs {} with {} tokens paid",
+                    tool.cost, tool.tokens
+                ));
+            }
+        }
+        // Every token was granted by a template, and is still held or has
+        // been paid onto a tool.
+        let granted: Option<u32> = self
+            .players
+            .iter()
+            .map(|p| p.template.as_ref()?.tokens.map(u32::from))
+            .sum();
+        if let Some(granted) = granted
+            && !self.is_solo()
+        {
+            let held: u32 = self.players.iter().map(|p| p.tokens as u32).sum();
+            let paid: u32 = self.tools.iter().map(|t| t.tokens as u32).sum();
+            if held + paid != granted {
+                return Err(format!(
+                    "{held} tokens held and {paid} paid, but templates \
+                     granted {granted}"
+                ));
+            }
+        }
+        for (i, obj) in self.objectives.iter().enumerate() {
+            if self.objectives[..i].iter().any(|o| {
+                std::mem::discriminant(o) == std::mem::discriminant(obj)
+            }) {
+                return Err(format!("duplicate objective {obj:?}"));
+            }
+        }
+        Ok(())
+    }
+    // Number of dice of each color taken out of the bag: on boards, in the
+    // draft pool, on the round track, or spent on solo tools.
+    fn dice_in_play(&self) -> [usize; NUM_COLORS] {
+        let mut counts = [0; NUM_COLORS];
+        for (color, _) in self.loose_dice() {
+            counts[color as usize] += 1;
+        }
+        for tool in self.tools.iter().filter(|t| t.used) {
+            counts[tool.tool_type.color() as usize] += 1;
+        }
+        counts
+    }
+    // Color and id of every die on a board, in the draft pool or on the
+    // round track.
+    fn loose_dice(&self) -> impl Iterator<Item = (Color, Option<DieId>)> {
+        let placed = self
+            .players
+            .iter()
+            .flat_map(|p| p.board.iter().flatten().filter_map(|c| c.die));
+        self.draft_pool.iter().map(|d| (d.color(), d.id())).chain(
+            placed
+                .chain(self.round_track.iter().flatten().copied())
+                .map(|d| (d.color, d.id())),
+        )
+    }
+    // Puts every die that isn't in play back in the bag. The bag is never
+    // serialized, so deserialized games are refilled this way. Dice in play
+    // without an id get a free one of their color.
+    fn refill_bag(&mut self) -> Result<(), DynError> {
+        let per_color =
+            dice_per_color(self.players.len(), self.rules.num_rounds);
+        let total = per_color * NUM_COLORS;
+        let mut used = vec![false; total];
+        for (_, id) in self.loose_dice() {
+            if let Some(id) = id.filter(|&id| (id as usize) < total) {
+                used[id as usize] = true;
+            }
+        }
+        let mut assign = |color: Color, id: &mut Option<DieId>| {
+            if id.is_none() {
+                *id = (0..total as DieId).find(|&free| {
+                    die_color(free) == color
+                        && !std::mem::replace(&mut used[free as usize], true)
+                });
+            }
+        };
+        for player in &mut self.players {
+            for die in player.board.iter_mut().flatten() {
+                if let Some(die) = &mut die.die {
+                    assign(die.color, die.id_mut());
+                }
+            }
+        }
+        for die in &mut self.draft_pool {
+            let color = die.color();
+            assign(color, die.id_mut());
+        }
+        for die in self.round_track.iter_mut().flatten() {
+            assign(die.color, die.id_mut());
+        }
+        self.dice_bag.clear();
+        for (color, count) in ALL_COLORS.iter().zip(self.dice_in_play()) {
+            let remaining = per_color.checked_sub(count).ok_or_else(|| {
+                format!(
+                    "Invalid game state: more than {per_color} {color:?} dice"
+                )
+            })?;
+            let free = (0..total as DieId)
+                .filter(|&id| die_color(id) == *color && !used[id as usize]);
+            self.dice_bag.extend(free.take(remaining));
+        }
+        self.dice_bag.shuffle(&mut self.rng);
+        Ok(())
+    }
     pub fn is_finished(&self) -> bool {
-        self.round_track.len() >= NUM_ROUNDS && self.draft_pool.is_empty()
+        self.round_track.len() >= self.rules.num_rounds
+            && self.draft_pool.is_empty()
     }
     fn next_idx(&self, idx: usize) -> usize {
         (idx + 1) % self.players.len()
@@ -90,23 +497,180 @@ impl GameState {
     fn prev_idx(&self, idx: usize) -> usize {
         (idx + self.players.len() - 1) % self.players.len()
     }
+    pub fn dice_in_bag(&self) -> usize {
+        self.dice_bag.len()
+    }
+    pub fn is_solo(&self) -> bool {
+        self.players.len() == 1
+    }
+    fn is_expansion(&self) -> bool {
+        self.players.len() >= EXPANSION_PLAYERS
+    }
+    // Number of dice rolled at the start of a round. Large tables roll half
+    // of the pool for each draft pass instead, to keep the pool manageable.
     fn pool_size(&self) -> usize {
-        2 * self.players.len() + 1
+        if self.is_solo() {
+            SOLO_POOL_SIZE
+        } else if self.is_expansion() {
+            self.players.len() + 1
+        } else {
+            2 * self.players.len() + 1
+        }
     }
     pub fn current_player(&self) -> &Player {
         &self.players[self.curr_player_idx]
     }
+    // Reseeds the RNG and reshuffles the bag, so that future rolls and draws
+    // can't be predicted from this copy of the game. The new seed is drawn
+    // from a fork of the RNG, so seeded games stay reproducible.
+    pub fn hide_future(&mut self) {
+        self.rng = StdRng::from_rng(&mut self.fork_rng());
+        self.dice_bag.shuffle(&mut self.rng);
+    }
+    // A copy of the game's RNG, for random choices outside the game that
+    // should still be reproducible in seeded games.
+    pub fn fork_rng(&self) -> StdRng {
+        self.rng.clone()
+    }
+    // Events from the most recent successful action, in order.
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+    // True while any player may pick their template, in any order.
+    pub fn selecting_simultaneously(&self) -> bool {
+        self.rules.simultaneous_selection
+            && self.phase == TurnPhase::SelectTemplate
+    }
+    // Index of the wildcard die the current player has drawn from the bag
+    // and must place or return before their turn ends.
+    pub fn pending_wild(&self) -> Option<usize> {
+        self.draft_pool.iter().position(|die| die.is_wild())
+    }
+    // Number of dice the given player can still draft before the game ends.
+    pub fn drafts_remaining(&self, player_idx: usize) -> usize {
+        let n = self.players.len();
+        let offset = |idx: usize| (idx + n - self.start_player_idx) % n;
+        let later_rounds = self
+            .rules
+            .num_rounds
+            .saturating_sub(self.round_track.len() + 1);
+        match self.phase {
+            TurnPhase::SelectTemplate => 2 * self.rules.num_rounds,
+            TurnPhase::FirstDraft => {
+                let drafted = offset(player_idx) < offset(self.curr_player_idx);
+                2 * later_rounds + if drafted { 1 } else { 2 }
+            }
+            TurnPhase::SecondDraft => {
+                let drafted = offset(player_idx) > offset(self.curr_player_idx);
+                2 * later_rounds + if drafted { 0 } else { 1 }
+            }
+            TurnPhase::GameOver => 0,
+        }
+    }
     pub fn take_turn(&mut self, action: &TurnAction) -> Result<bool, DynError> {
+        self.take_turn_as(self.curr_player_idx, action)
+    }
+    // Like `take_turn`, but for a given player. Only the current player may
+    // act, except while templates are picked simultaneously.
+    pub fn take_turn_as(
+        &mut self,
+        player_idx: usize,
+        action: &TurnAction,
+    ) -> Result<bool, DynError> {
+        if player_idx >= self.players.len() {
+            return Err("Invalid player index".into());
+        }
+        if player_idx != self.curr_player_idx
+            && !self.selecting_simultaneously()
+        {
+            return Err("Not your turn".into());
+        }
+        self.events.clear();
+        let done = self.apply_turn(player_idx, action)?;
+        if done {
+            let scores = self.player_scores();
+            let mut standings: Vec<usize> = (0..scores.len()).collect();
+            standings.sort_by_key(|&i| std::cmp::Reverse(scores[i].total()));
+            self.events.push(GameEvent::GameEnded {
+                scores,
+                standings,
+                winner: self.winner_idx(),
+            });
+        } else if !matches!(action.idx, ActionType::UseTool(_))
+            && !self.selecting_simultaneously()
+        {
+            self.events.push(GameEvent::TurnStarted {
+                player: self.curr_player_idx,
+                phase: self.phase,
+            });
+        }
+        // The action has already been applied, so a broken invariant is an
+        // engine bug rather than a rejected move.
+        debug_assert!(
+            self.validate().is_ok(),
+            "{:?} after {action:?}",
+            self.validate()
+        );
+        Ok(done)
+    }
+    fn apply_turn(
+        &mut self,
+        player_idx: usize,
+        action: &TurnAction,
+    ) -> Result<bool, DynError> {
         match self.phase {
             TurnPhase::SelectTemplate => {
                 if let ActionType::SelectTemplate(idx) = action.idx {
-                    self.players[self.curr_player_idx].select_template(idx)?;
+                    let solo = self.is_solo();
+                    let player = &mut self.players[player_idx];
+                    if player.templates.is_empty() {
+                        return Err("Template already selected".into());
+                    }
+                    player.select_template(idx)?;
+                    // Solo tools are paid for with dice instead of tokens.
+                    if solo {
+                        player.tokens = 0;
+                    }
+                    let template = player.chosen_template();
+                    self.events.push(if self.rules.simultaneous_selection {
+                        GameEvent::TemplatePicked {
+                            player: player_idx,
+                            template: Some(template),
+                        }
+                    } else {
+                        GameEvent::TemplateSelected {
+                            player: player_idx,
+                            template,
+                        }
+                    });
                 } else {
                     return Err("Invalid action: must select a template".into());
                 }
-                self.curr_player_idx = self.next_idx(self.curr_player_idx);
-                if self.curr_player_idx == self.start_player_idx {
-                    self.start_round();
+                // Turn order still decides who the game reports as waiting
+                // on when picks are simultaneous.
+                let n = self.players.len();
+                let next = (1..=n)
+                    .map(|i| (self.curr_player_idx + i) % n)
+                    .take_while(|&i| {
+                        i != self.start_player_idx
+                            || self.rules.simultaneous_selection
+                    })
+                    .find(|&i| !self.players[i].templates.is_empty());
+                match next {
+                    Some(idx) => self.curr_player_idx = idx,
+                    None => {
+                        // Simultaneous picks are revealed together.
+                        if self.rules.simultaneous_selection {
+                            for (i, player) in self.players.iter().enumerate() {
+                                self.events.push(GameEvent::TemplateSelected {
+                                    player: i,
+                                    template: player.chosen_template(),
+                                });
+                            }
+                        }
+                        self.curr_player_idx = self.start_player_idx;
+                        self.start_round();
+                    }
                 }
             }
             TurnPhase::FirstDraft => {
@@ -116,6 +680,9 @@ impl GameState {
                         self.curr_player_idx =
                             self.prev_idx(self.curr_player_idx);
                         self.phase = TurnPhase::SecondDraft;
+                        if self.is_expansion() {
+                            self.roll_dice(self.players.len());
+                        }
                     }
                 }
             }
@@ -139,6 +706,7 @@ impl GameState {
         Ok(matches!(self.phase, TurnPhase::GameOver))
     }
     fn handle_action(&mut self, action: &TurnAction) -> Result<bool, DynError> {
+        let action = &self.resolve_die_id(action)?;
         match action.idx {
             ActionType::SelectTemplate(_) => {
                 Err("Invalid action: templates have already been selected"
@@ -150,9 +718,55 @@ impl GameState {
             ActionType::UseTool(idx) => {
                 let data =
                     action.tool.as_ref().ok_or("Tool action missing data")?;
-                self.handle_tool(idx, data)
+                self.handle_tool(idx, data, action.payment)
+            }
+        }
+    }
+    // Points an action that names its dice by id at those dice's current
+    // indices in the pool or on the round track.
+    fn resolve_die_id(
+        &self,
+        action: &TurnAction,
+    ) -> Result<TurnAction, DynError> {
+        let mut action = action.clone();
+        let pool_idx = |id: DieId| {
+            self.draft_pool
+                .iter()
+                .position(|die| die.id() == Some(id))
+                .ok_or_else(|| format!("Die {id} is not in the draft pool"))
+        };
+        if let Some(id) = action.die_id {
+            let idx = pool_idx(id)?;
+            match (&mut action.idx, &mut action.tool) {
+                (ActionType::DraftDie(draft_idx, _), _) => *draft_idx = idx,
+                (ActionType::UseTool(_), Some(data)) => {
+                    *data
+                        .draft_idx_mut()
+                        .ok_or("Tool doesn't use a pool die")? = idx;
+                }
+                _ => return Err("Action doesn't use a pool die".into()),
             }
         }
+        if let Some(id) = action.payment_id {
+            action.payment = Some(pool_idx(id)?);
+        }
+        if let Some(ToolData::SwapDraftedDieWithRoundTrack {
+            round_idx,
+            round_die_id: Some(id),
+            ..
+        }) = &mut action.tool
+        {
+            *round_idx = self
+                .round_track
+                .iter()
+                .enumerate()
+                .find_map(|(round, dice)| {
+                    let i = dice.iter().position(|d| d.id() == Some(*id))?;
+                    Some((round, i))
+                })
+                .ok_or_else(|| format!("Die {id} is not on the round track"))?;
+        }
+        Ok(action)
     }
     fn handle_draft_die(
         &mut self,
@@ -160,25 +774,43 @@ impl GameState {
         face: Option<u8>,
         coords: Option<(usize, usize)>,
     ) -> Result<bool, DynError> {
-        if let Some(coords) = coords {
-            let mut die =
-                *self.draft_pool.get(idx).ok_or("Invalid die index")?;
-            match (face, die.face) {
-                (Some(f), 0) => {
-                    die.face = f;
-                }
-                (Some(_), _) => {
-                    return Err(
-                        "Cannot choose face for a non-wildcard die".into()
-                    );
-                }
-                (None, 0) => {
-                    return Err("Must choose face for a wildcard die".into());
+        let wild = self.pending_wild();
+        if wild.is_some_and(|w| w != idx) {
+            return Err("Must place or return the wildcard die".into());
+        }
+        match (coords, wild) {
+            (Some(coords), _) => {
+                let die = self
+                    .draft_pool
+                    .get(idx)
+                    .ok_or("Invalid die index")?
+                    .with_face(face)?;
+                self.players[self.curr_player_idx].place_die(coords, die)?;
+                self.draft_pool.remove(idx);
+                self.events.push(GameEvent::DieDrafted {
+                    player: self.curr_player_idx,
+                    die,
+                    coords,
+                });
+            }
+            // The wildcard goes back to the pool with its chosen face, but
+            // only if that face can't be placed anywhere.
+            (None, Some(_)) => {
+                let die = self.draft_pool[idx].with_face(face)?;
+                if self.current_player().can_place_anywhere(die) {
+                    return Err("The wildcard die must be placed".into());
                 }
-                (None, _) => { /* Use existing face */ }
+                self.draft_pool[idx] = die.into();
+                self.events.push(GameEvent::DieReturned {
+                    player: self.curr_player_idx,
+                    die,
+                });
+            }
+            (None, None) => {
+                self.events.push(GameEvent::Passed {
+                    player: self.curr_player_idx,
+                });
             }
-            self.players[self.curr_player_idx].place_die(coords, die)?;
-            self.draft_pool.remove(idx);
         }
         self.players[self.curr_player_idx].active_tool = None;
         Ok(true)
@@ -187,52 +819,115 @@ impl GameState {
         &mut self,
         idx: usize,
         data: &ToolData,
+        payment: Option<usize>,
     ) -> Result<bool, DynError> {
-        let tool = self.tools.get(idx).ok_or("Invalid tool index")?;
-        self.players[self.curr_player_idx].can_use_tool(tool)?;
-        let mut rng = rand::rng();
+        self.can_use_tool(idx)?;
+        let tool = self.tools[idx].clone();
+        if self.is_solo() {
+            let payment =
+                payment.ok_or("Solo tools must be paid with a die")?;
+            if !self.payment_options(idx, data).contains(&payment) {
+                return Err("Invalid die to pay for tool".into());
+            }
+        } else if payment.is_some() {
+            return Err("Tools are paid with tokens, not dice".into());
+        }
+        // The spent die leaves the game before the tool takes effect, so the
+        // tool's own pool index shifts down if it came after it.
+        let paid = payment.map(|payment| self.draft_pool.remove(payment));
+        let mut effect = data.clone();
+        if let Some(payment) = payment
+            && let Some(draft_idx) = effect.draft_idx_mut()
+            && *draft_idx > payment
+        {
+            *draft_idx -= 1;
+        }
+        if let Err(e) = self.apply_tool(&effect) {
+            if let (Some(payment), Some(die)) = (payment, paid) {
+                self.draft_pool.insert(payment, die);
+            }
+            return Err(e);
+        }
+        self.players[self.curr_player_idx].active_tool = Some(tool.tool_type);
+        if paid.is_some() {
+            self.tools[idx].used = true;
+        } else {
+            self.players[self.curr_player_idx].tokens -= tool.cost;
+            self.tools[idx].tokens =
+                self.tools[idx].tokens.saturating_add(tool.cost);
+            if tool.cost == self.rules.tool_cost {
+                self.tools[idx].cost = self.rules.tool_cost.saturating_mul(2);
+            }
+        }
+        let round_track_die = match *data {
+            ToolData::SwapDraftedDieWithRoundTrack { round_idx, .. } => {
+                Some((round_idx, self.round_track[round_idx.0][round_idx.1]))
+            }
+            _ => None,
+        };
+        self.events.push(GameEvent::ToolUsed {
+            player: self.curr_player_idx,
+            tool: idx,
+            data: data.clone(),
+            tokens: if paid.is_some() { 0 } else { tool.cost },
+            payment: paid,
+            draft_pool: self.draft_pool.clone(),
+            round_track_die,
+        });
+        Ok(false)
+    }
+    // Applies a tool's effect to the draft pool or round track.
+    fn apply_tool(&mut self, data: &ToolData) -> Result<(), DynError> {
+        let rng = &mut self.rng;
         match data {
             ToolData::RerollAllDiceInPool => {
-                self.draft_pool
-                    .iter_mut()
-                    .for_each(|die| die.reroll(&mut rng));
+                self.draft_pool.iter_mut().for_each(|die| die.reroll(rng));
             }
             ToolData::PlaceIgnoringAdjacency => {}
             ToolData::FlipDraftedDie { draft_idx } => {
-                self.draft_pool
+                match self
+                    .draft_pool
                     .get_mut(*draft_idx)
                     .ok_or("Invalid draft index")?
-                    .flip();
+                {
+                    PoolDie::Rolled(die) => die.flip(),
+                    PoolDie::Wild(..) => {
+                        return Err("Cannot flip a wildcard die".into());
+                    }
+                }
             }
             ToolData::RerollDraftedDie { draft_idx } => {
                 self.draft_pool
                     .get_mut(*draft_idx)
                     .ok_or("Invalid draft index")?
-                    .reroll(&mut rng);
+                    .reroll(rng);
             }
             ToolData::BumpDraftedDie {
                 draft_idx,
                 is_increment,
             } => {
-                let face = self
+                let PoolDie::Rolled(die) = self
                     .draft_pool
-                    .get(*draft_idx)
+                    .get_mut(*draft_idx)
                     .ok_or("Invalid draft index")?
-                    .face;
-                match (*is_increment, face) {
+                else {
+                    return Err("Cannot bump a wildcard die".into());
+                };
+                match (*is_increment, die.face) {
                     (true, 6) => {
                         return Err("Cannot increment a die past 6".into());
                     }
                     (false, 1) => {
                         return Err("Cannot decrement a die below 1".into());
                     }
-                    (true, _) => self.draft_pool[*draft_idx].increment(),
-                    (false, _) => self.draft_pool[*draft_idx].decrement(),
+                    (true, _) => die.increment(),
+                    (false, _) => die.decrement(),
                 }
             }
             ToolData::SwapDraftedDieWithRoundTrack {
                 draft_idx,
                 round_idx,
+                ..
             } => {
                 let src = self
                     .round_track
@@ -244,28 +939,30 @@ impl GameState {
                     .draft_pool
                     .get_mut(*draft_idx)
                     .ok_or("Invalid draft pool index")?;
-                std::mem::swap(src, dst);
+                let die = dst.rolled().ok_or("Cannot swap a wildcard die")?;
+                *dst = std::mem::replace(src, die).into();
             }
             ToolData::SwapDraftedDieWithBag { draft_idx } => {
                 let die = self
                     .draft_pool
                     .get_mut(*draft_idx)
                     .ok_or("Invalid draft pool index")?;
-                let color = self.dice_bag.pop().ok_or("Dice bag is empty")?;
-                self.dice_bag.push(die.color);
-                die.color = color;
-                die.face = 0; // Wildcard face; player will choose later
+                let old = die.id().ok_or("Die has no id")?;
+                let id = self.dice_bag.pop().ok_or("Dice bag is empty")?;
+                self.dice_bag.push(old);
+                // The player picks a face when placing or returning it.
+                *die = PoolDie::Wild(die_color(id), Some(id));
             }
             ToolData::MoveDieIgnoringColor { from } => {
-                todo!("Implement tool: {:?} ({from:?})", tool.tool_type);
+                todo!("Implement tool: {:?} ({from:?})", data.tool_type());
             }
             ToolData::MoveDieIgnoringValue { from } => {
-                todo!("Implement tool: {:?} ({from:?})", tool.tool_type);
+                todo!("Implement tool: {:?} ({from:?})", data.tool_type());
             }
             ToolData::MoveExactlyTwoDice { from, to } => {
                 todo!(
                     "Implement tool: {:?} ({from:?}, {to:?})",
-                    tool.tool_type
+                    data.tool_type()
                 );
             }
             ToolData::MoveUpToTwoDiceMatchingColor {
@@ -275,51 +972,124 @@ impl GameState {
             } => {
                 todo!(
                     "Implement tool: {:?} ({from:?}, {to:?}, {round_idx:?})",
-                    tool.tool_type
+                    data.tool_type()
                 );
             }
             ToolData::DraftTwoDice => {
-                todo!("Implement tool: {:?}", tool.tool_type);
+                todo!("Implement tool: {:?}", data.tool_type());
             }
         }
-        self.players[self.curr_player_idx].active_tool = Some(tool.tool_type);
-        self.players[self.curr_player_idx].tokens -= tool.cost;
-        if tool.cost == 1 {
-            self.tools[idx].cost = 2;
+        Ok(())
+    }
+    // Checks whether the current player can afford the given tool.
+    pub fn can_use_tool(&self, idx: usize) -> Result<(), DynError> {
+        let tool = self.tools.get(idx).ok_or("Invalid tool index")?;
+        let player = self.current_player();
+        if !self.is_solo() {
+            return player.can_use_tool(tool);
         }
-        Ok(false)
+        if player.active_tool.is_some() {
+            return Err("Already used a tool this turn".into());
+        }
+        if tool.used {
+            return Err("Tool has already been used".into());
+        }
+        let color = tool.tool_type.color();
+        if !self.draft_pool.iter().any(|die| die.color() == color) {
+            return Err("No die of the tool's color to pay with".into());
+        }
+        Ok(())
+    }
+    // Draft pool dice that could pay for a tool in a solo game. The die the
+    // tool acts on can't also be spent on it.
+    pub fn payment_options(&self, idx: usize, data: &ToolData) -> Vec<usize> {
+        let Some(tool) = self.tools.get(idx) else {
+            return vec![];
+        };
+        if !self.is_solo() {
+            return vec![];
+        }
+        let color = tool.tool_type.color();
+        self.draft_pool
+            .iter()
+            .enumerate()
+            .filter(|(i, die)| {
+                die.color() == color && Some(*i) != data.draft_idx()
+            })
+            .map(|(i, _)| i)
+            .collect()
     }
     fn start_round(&mut self) {
-        let mut rng = rand::rng();
-        self.draft_pool = self
+        self.roll_dice(self.pool_size());
+        self.phase = TurnPhase::FirstDraft;
+    }
+    // Moves dice from the bag to the draft pool.
+    fn roll_dice(&mut self, count: usize) {
+        let split = self.dice_bag.len().saturating_sub(count);
+        let rng = &mut self.rng;
+        let dice: Vec<PoolDie> = self
             .dice_bag
-            .split_off(self.dice_bag.len() - self.pool_size())
+            .split_off(split)
             .into_iter()
-            .map(|color| Dice::roll(color, &mut rng))
+            .map(|id| PoolDie::Rolled(Dice::roll(id, rng)))
             .collect();
-        self.phase = TurnPhase::FirstDraft;
+        self.draft_pool.extend_from_slice(&dice);
+        self.events.push(GameEvent::DiceRolled { dice });
     }
     fn finish_round(&mut self) {
-        // Any remaining dice in the draft pool are moved to the round track.
-        self.round_track.push(self.draft_pool.drain(..).collect());
+        // Any remaining dice in the draft pool are moved to the round track,
+        // and any wildcard left there is rolled first.
+        let rng = &mut self.rng;
+        let leftover: Vec<Dice> = self
+            .draft_pool
+            .drain(..)
+            .map(|die| die.roll_if_wild(rng))
+            .collect();
+        self.round_track.push(leftover.clone());
+        self.events.push(GameEvent::RoundEnded {
+            round: self.round_track.len(),
+            leftover,
+        });
         self.start_player_idx = self.next_idx(self.start_player_idx);
         self.curr_player_idx = self.start_player_idx;
     }
     pub fn player_scores(&self) -> Vec<ScoreBreakdown> {
+        if self.is_solo() {
+            let target = self.solo_target();
+            return vec![
+                self.players[0].calculate_solo_score(&self.objectives, target),
+            ];
+        }
         self.players
             .iter()
-            .map(|player| player.calculate_score(&self.objectives))
+            .map(|player| player.calculate_score(&self.objectives, &self.rules))
             .collect()
     }
+    // Points lost for each slot left empty at the end of the game.
+    pub fn empty_slot_penalty(&self) -> i32 {
+        if self.is_solo() {
+            SOLO_EMPTY_SLOT_PENALTY
+        } else {
+            self.rules.empty_slot_penalty
+        }
+    }
+    // Solo games are won by beating the sum of the dice on the round track.
+    pub fn solo_target(&self) -> i32 {
+        self.round_track
+            .iter()
+            .flatten()
+            .map(|die| die.face as i32)
+            .sum()
+    }
     pub fn winner_idx(&self) -> Option<usize> {
         if !self.is_finished() {
             return None;
         }
-        let scores: Vec<i32> = self
-            .players
-            .iter()
-            .map(|player| player.calculate_score(&self.objectives).total())
-            .collect();
+        let scores: Vec<i32> =
+            self.player_scores().iter().map(|s| s.total()).collect();
+        if self.is_solo() {
+            return (scores[0] > self.solo_target()).then_some(0);
+        }
         let max_score = *scores.iter().max().unwrap();
         let max_indices: Vec<usize> = scores
             .iter()
@@ -336,23 +1106,176 @@ impl GameState {
                 self.players[i].secret = c;
             }
         }
+        self.hide_template_picks(Some(exclude_idx));
+    }
+    // Spectators see no secret colors at all.
+    pub fn hide_secrets(&mut self) {
+        for player in &mut self.players {
+            player.secret = None;
+            player.second_secret = None;
+        }
+        self.hide_template_picks(None);
+    }
+    // During simultaneous selection, others only see who has picked a
+    // template, not which one.
+    pub fn hide_template_picks(&mut self, viewer: Option<usize>) {
+        if !self.selecting_simultaneously() {
+            return;
+        }
+        for (i, player) in self.players.iter_mut().enumerate() {
+            if Some(i) != viewer && player.templates.is_empty() {
+                player.tokens = 0;
+                player.board = Default::default();
+                player.template = None;
+            }
+        }
+    }
+}
+
+/// Sets up a game in an arbitrary mid-game situation, e.g. for tests.
+/// Players start with an empty board, no tokens and distinct secrets, and
+/// the game starts in the first draft of round one.
+pub struct GameStateBuilder {
+    state: GameState,
+    error: Option<String>,
+}
+impl GameStateBuilder {
+    pub fn new(num_players: usize) -> Self {
+        let players = (0..num_players)
+            .map(|i| Player {
+                tokens: 0,
+                board: [[BoardCell::default(); BOARD_COLS]; BOARD_ROWS],
+                secret: Some(ALL_COLORS[i % NUM_COLORS]),
+                second_secret: (num_players == 1).then_some(ALL_COLORS[1]),
+                templates: Vec::new(),
+                template: None,
+                active_tool: None,
+            })
+            .collect();
+        Self {
+            state: GameState {
+                players,
+                start_player_idx: 0,
+                curr_player_idx: 0,
+                phase: TurnPhase::FirstDraft,
+                dice_bag: Vec::new(),
+                draft_pool: Vec::new(),
+                round_track: Vec::new(),
+                tools: Vec::new(),
+                objectives: Vec::new(),
+                rules: Rules::default(),
+                rng: StdRng::seed_from_u64(0),
+                events: Vec::new(),
+            },
+            error: None,
+        }
+    }
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.state.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+    pub fn rules(mut self, rules: Rules) -> Self {
+        self.state.rules = rules;
+        self
+    }
+    pub fn player(mut self, idx: usize, player: Player) -> Self {
+        self.with_player(idx, |p| *p = player);
+        self
+    }
+    pub fn board(mut self, idx: usize, board: Board) -> Self {
+        self.with_player(idx, |p| p.board = board);
+        self
+    }
+    pub fn tokens(mut self, idx: usize, tokens: u8) -> Self {
+        self.with_player(idx, |p| p.tokens = tokens);
+        self
+    }
+    pub fn secret(mut self, idx: usize, secret: Color) -> Self {
+        self.with_player(idx, |p| p.secret = Some(secret));
+        self
+    }
+    pub fn draft_pool(mut self, dice: Vec<PoolDie>) -> Self {
+        self.state.draft_pool = dice;
+        self
+    }
+    pub fn round_track(mut self, rounds: Vec<Vec<Dice>>) -> Self {
+        self.state.round_track = rounds;
+        self
+    }
+    pub fn tools(mut self, tools: Vec<Tool>) -> Self {
+        self.state.tools = tools;
+        self
+    }
+    pub fn objectives(mut self, objectives: Vec<Objective>) -> Self {
+        self.state.objectives = objectives;
+        self
+    }
+    pub fn phase(mut self, phase: TurnPhase) -> Self {
+        self.state.phase = phase;
+        self
+    }
+    // The player who drafts first this round; defaults to player 0.
+    pub fn start_player(mut self, idx: usize) -> Self {
+        self.state.start_player_idx = idx;
+        self
+    }
+    pub fn current_player(mut self, idx: usize) -> Self {
+        self.state.curr_player_idx = idx;
+        self
+    }
+    // Fills the dice bag with every die not in play and checks the game.
+    pub fn build(self) -> Result<GameState, DynError> {
+        if let Some(msg) = self.error {
+            return Err(format!("Invalid game state: {msg}").into());
+        }
+        let mut state = self.state;
+        state.refill_bag()?;
+        state.validate()?;
+        Ok(state)
+    }
+    fn with_player(&mut self, idx: usize, f: impl FnOnce(&mut Player)) {
+        match self.state.players.get_mut(idx) {
+            Some(player) => f(player),
+            None => {
+                self.error.get_or_insert(format!("no player {idx}"));
+            }
+        }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Player {
-    tokens: u8,
-    board: [[BoardCell; BOARD_COLS]; BOARD_ROWS],
-    secret: Color,
+    pub(crate) tokens: u8,
+    pub(crate) board: [[BoardCell; BOARD_COLS]; BOARD_ROWS],
+    // None only in views for spectators, who see no secret colors.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub(crate) secret: Option<Color>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub(crate) second_secret: Option<Color>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub templates: Vec<BoardTemplate>,
-    active_tool: Option<ToolType>,
+    // The template this player's board was built from.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub template: Option<TemplateInfo>,
+    pub(crate) active_tool: Option<ToolType>,
 }
 impl Player {
+    // What others see of the template this player selected.
+    fn chosen_template(&self) -> ChosenTemplate {
+        ChosenTemplate {
+            info: self.template.clone().unwrap_or_default(),
+            slots: self.board.map(|row| row.map(|cell| cell.slot)),
+            tokens: self.tokens,
+        }
+    }
     fn select_template(&mut self, idx: usize) -> Result<(), DynError> {
         let template =
             self.templates.get(idx).ok_or("Invalid template index")?;
         self.tokens = template.value;
+        self.template = Some(TemplateInfo {
+            tokens: Some(template.value),
+            ..template.info.clone()
+        });
         for i in 0..BOARD_ROWS {
             for j in 0..BOARD_COLS {
                 self.board[i][j].slot = template.slots[i][j];
@@ -362,13 +1285,7 @@ impl Player {
         Ok(())
     }
     pub fn pretty_print(&self) {
-        println!("Tokens: {}", self.tokens);
-        for row in &self.board {
-            for cell in row {
-                print!("{} ", cell);
-            }
-            println!();
-        }
+        print!("{self}");
     }
 
     pub fn can_place_die(
@@ -426,6 +1343,34 @@ impl Player {
         }
         Ok(())
     }
+    pub fn can_place_anywhere(&self, die: Dice) -> bool {
+        (0..BOARD_ROWS).any(|r| {
+            (0..BOARD_COLS).any(|c| self.can_place_die((r, c), die).is_ok())
+        })
+    }
+    // Checks that every placed die obeys its slot and its neighbors.
+    fn check_board(&self) -> Result<(), String> {
+        for (i, row) in self.board.iter().enumerate() {
+            for (j, cell) in row.iter().enumerate() {
+                let Some(die) = cell.die else { continue };
+                if !(1..=6).contains(&die.face) || !cell.slot.accepts(die) {
+                    return Err(format!("{die} can't be at {:?}", (i, j)));
+                }
+                let clash = neighbor_coords((i, j)).any(|(r, c)| {
+                    self.board[r][c].die.is_some_and(|nbr| {
+                        nbr.color == die.color || nbr.face == die.face
+                    })
+                });
+                if clash {
+                    return Err(format!(
+                        "{die} at {:?} matches a neighbor",
+                        (i, j)
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
     fn place_die(
         &mut self,
         coords: (usize, usize),
@@ -444,23 +1389,28 @@ impl Player {
         }
         Ok(())
     }
-    fn calculate_score(&self, objectives: &[Objective]) -> ScoreBreakdown {
+    fn calculate_score(
+        &self,
+        objectives: &[Objective],
+        rules: &Rules,
+    ) -> ScoreBreakdown {
         let mut score = ScoreBreakdown::default();
         // Score the objectives.
-        for (i, obj) in objectives.iter().enumerate() {
-            score.objectives[i] = obj.score(&self.board);
-        }
+        score.objectives = objectives
+            .iter()
+            .map(|obj| obj.score(&self.board))
+            .collect();
         for cell in self.board.iter().flatten() {
             match cell.die {
                 // One point for each pip matching our secret color
                 Some(die) => {
-                    if die.color == self.secret {
+                    if Some(die.color) == self.secret {
                         score.secret_color += die.face as i32;
                     }
                 }
-                // Minus one point for each slot without a die in it
+                // Lose points for each slot without a die in it
                 None => {
-                    score.empty_slots -= 1;
+                    score.empty_slots -= rules.empty_slot_penalty;
                 }
             }
         }
@@ -468,14 +1418,95 @@ impl Player {
         score.unused_tokens = self.tokens as i32;
         score
     }
+    // Solo scoring: the better of the two secret colors counts, there are no
+    // tokens, and empty slots cost more.
+    fn calculate_solo_score(
+        &self,
+        objectives: &[Objective],
+        target: i32,
+    ) -> ScoreBreakdown {
+        let pips = |color: Color| -> i32 {
+            self.board
+                .iter()
+                .flatten()
+                .filter_map(|cell| cell.die)
+                .filter(|die| die.color == color)
+                .map(|die| die.face as i32)
+                .sum()
+        };
+        let empty = self
+            .board
+            .iter()
+            .flatten()
+            .filter(|cell| cell.die.is_none())
+            .count() as i32;
+        ScoreBreakdown {
+            objectives: objectives
+                .iter()
+                .map(|obj| obj.score(&self.board))
+                .collect(),
+            secret_color: self
+                .secret
+                .map_or(0, pips)
+                .max(self.second_secret.map_or(0, pips)),
+            unused_tokens: 0,
+            empty_slots: -SOLO_EMPTY_SLOT_PENALTY * empty,
+            target: Some(target),
+        }
+    }
+}
+
+impl Display for Player {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Tokens: {}", self.tokens)?;
+        for row in &self.board {
+            for cell in row {
+                write!(f, "{} ", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+impl FromStr for Player {
+    type Err = String;
+    // Parses the output of `pretty_print`, plus an optional `Secret: C` line.
+    // The secret color defaults to red.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (headers, grid) = split_headers(s);
+        let mut player = Player {
+            tokens: 0,
+            board: parse_grid(grid)?,
+            secret: Some(Color::Red),
+            second_secret: None,
+            templates: Vec::new(),
+            template: None,
+            active_tool: None,
+        };
+        for (key, val) in headers {
+            match key {
+                "Tokens" => {
+                    player.tokens = val
+                        .parse()
+                        .map_err(|_| format!("Invalid tokens: {val}"))?;
+                }
+                "Secret" => player.secret = Some(val.parse()?),
+                _ => return Err(format!("Unknown header: {key}")),
+            }
+        }
+        Ok(player)
+    }
 }
 
-#[derive(Serialize, Deserialize, Default, Debug)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct ScoreBreakdown {
-    pub objectives: [i32; NUM_OBJECTIVES],
+    pub objectives: Vec<i32>,
     pub secret_color: i32,
     pub unused_tokens: i32,
     pub empty_slots: i32,
+    // Solo games only: the score to beat.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub target: Option<i32>,
 }
 impl ScoreBreakdown {
     pub fn total(&self) -> i32 {
@@ -486,7 +1517,7 @@ impl ScoreBreakdown {
     }
 }
 
-fn neighbor_coords(
+pub(crate) fn neighbor_coords(
     coords: (usize, usize),
 ) -> impl Iterator<Item = (usize, usize)> {
     let (r, c) = coords;
@@ -500,7 +1531,7 @@ fn neighbor_coords(
     .filter(|(r, c)| *r < BOARD_ROWS && *c < BOARD_COLS)
 }
 
-fn diagonal_coords(
+pub(crate) fn diagonal_coords(
     coords: (usize, usize),
 ) -> impl Iterator<Item = (usize, usize)> {
     let (r, c) = coords;
@@ -513,3 +1544,322 @@ fn diagonal_coords(
     .into_iter()
     .filter(|(r, c)| *r < BOARD_ROWS && *c < BOARD_COLS)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::parse_board;
+
+    fn dice<T: FromStr<Err = String>>(text: &str) -> Vec<T> {
+        text.split_whitespace()
+            .map(|d| d.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_builder_scenario() -> Result<(), DynError> {
+        let board = parse_board(
+            "
+            R1 __ __ __ __
+            __ __ __ __ __
+            __ __ __ __ __
+            __ __ __ __ G_
+            ",
+        )?;
+        let mut game = GameState::builder(2)
+            .board(0, board)
+            .tokens(0, 2)
+            .secret(1, Color::Green)
+            .draft_pool(dice("R2 Y2 G3"))
+            .round_track(vec![dice("P6")])
+            .tools(vec![Tool {
+                tool_type: ToolType::FlipDraftedDie,
+                cost: 2,
+                tokens: 1,
+                used: false,
+            }])
+            .objectives(vec![Objective::Pair12(2)])
+            .phase(TurnPhase::SecondDraft)
+            .build()?;
+        assert_eq!(game.dice_in_bag(), 5 * dice_per_color(2, 10) - 5);
+        assert_eq!(game.drafts_remaining(0), 2 * 8 + 1);
+        assert_eq!(game.drafts_remaining(1), 2 * 8);
+        // The start player drafts last in the second pass, ending the round.
+        game.take_turn(&TurnAction::new(
+            ActionType::DraftDie(2, None),
+            Some((1, 1)),
+        ))?;
+        assert_eq!(game.round_track.len(), 2);
+        assert_eq!(game.curr_player_idx, 1);
+        assert_eq!(game.players[0].board[1][1].to_string(), "G3");
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        let check = |builder: GameStateBuilder| {
+            builder
+                .build()
+                .err()
+                .map(|e| e.to_string())
+                .unwrap_or_default()
+        };
+        let board = |text: &str| parse_board(text).unwrap();
+        assert!(
+            check(GameState::builder(2).tokens(2, 1)).contains("no player 2")
+        );
+        assert!(
+            check(GameState::builder(2).current_player(3))
+                .contains("out of range")
+        );
+        assert!(
+            check(GameState::builder(2).board(
+                0,
+                board("R1 R2 __ __ __\n__ __ __ __ __\n__ __ __ __ __\n__ __ __ __ __")
+            ))
+            .contains("matches a neighbor")
+        );
+        assert!(
+            check(GameState::builder(2).board(
+                1,
+                board("__ __ __ __ __\nB_ __ __ __ __\nY3 __ __ __ __\n__ __ __ __ __")
+            ))
+            .is_empty()
+        );
+        let mut wrong_face = board(
+            "__ __ __ __ __\n__ __ __ __ __\n__ __ __ __ __\n__ _4 __ __ __",
+        );
+        wrong_face[3][1].die = Some("P5".parse().unwrap());
+        assert!(
+            check(GameState::builder(2).board(1, wrong_face))
+                .contains("can't be at (3, 1)")
+        );
+        let tool = Tool {
+            tool_type: ToolType::FlipDraftedDie,
+            cost: 1,
+            tokens: 0,
+            used: false,
+        };
+        assert!(
+            check(GameState::builder(2).tools(vec![tool.clone(), tool]))
+                .contains("duplicate tool")
+        );
+        assert!(
+            check(GameState::builder(2).round_track(vec![dice("B6"); 10]))
+                .contains("10 rounds")
+        );
+        assert!(
+            check(GameState::builder(2).draft_pool(dice("B1 B2 B3 B4 B5 B6")))
+                .contains("more than 5 dice")
+        );
+        assert!(
+            check(GameState::builder(2).phase(TurnPhase::GameOver))
+                .contains("game over")
+        );
+    }
+
+    #[test]
+    fn test_strict_deserialization() -> Result<(), DynError> {
+        let game = GameState::builder(2)
+            .board(
+                0,
+                parse_board(
+                    "R1 __ __ __ __\n__ __ __ __ __\n__ __ __ __ __\n__ __ __ __ __",
+                )?,
+            )
+            .draft_pool(dice("Y2 G3"))
+            .build()?;
+        let json = serde_json::to_string(&game)?;
+        let restored: GameState = serde_json::from_str(&json)?;
+        assert_eq!(restored.draft_pool[1].to_string(), "G3");
+        // Dice built without ids are given one; ids then survive a restore.
+        assert_eq!(game.draft_pool[1].id(), Some(2));
+        assert_eq!(restored.draft_pool[1].id(), Some(2));
+        let check = |from: &str, to: &str| {
+            assert!(json.contains(from), "{from}");
+            serde_json::from_str::<GameState>(&json.replacen(from, to, 1))
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(check(r#""Y2#"#, r#""Y9#"#).contains("face must be 1 to 6"));
+        assert!(check(r#""Y2#"#, r#""Y0#"#).contains("wildcard die left"));
+        assert!(check(r#""R1#"#, r#""R*#"#).contains("face must be 1 to 6"));
+        assert!(check(r#""G3#"#, r#""G*#"#).contains("wildcard die left"));
+        assert!(check(r#""G3#2""#, r#""G3#1""#).contains("Green die has id 1"));
+        assert!(check(r#""G3#2""#, r#""G3#x""#).contains("bad id"));
+        assert!(check(r#""slot":null"#, r#""slot":9"#).contains("slot face 9"));
+        assert!(
+            check(r#""slot":null"#, r#""slot":"X""#).contains("slot color")
+        );
+        assert!(
+            check(r#""curr_player_idx":0"#, r#""curr_player_idx":7"#)
+                .contains("out of range")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_hide_future() -> Result<(), DynError> {
+        let game = GameState::init_with_seed(2, 4)?;
+        let mut hidden = game.clone();
+        hidden.hide_future();
+        assert_ne!(hidden.dice_bag, game.dice_bag);
+        let mut bag = hidden.dice_bag.clone();
+        bag.sort();
+        assert_eq!(bag, (0..bag.len() as DieId).collect::<Vec<_>>());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tokens_are_conserved() -> Result<(), DynError> {
+        let mut game = GameState::init_with_seed(2, 1)?;
+        for _ in 0..2 {
+            game.take_turn(&TurnAction::new(
+                ActionType::SelectTemplate(0),
+                None,
+            ))?;
+        }
+        let tokens = game.players[0].tokens;
+        let json = serde_json::to_string(&game)?;
+        serde_json::from_str::<GameState>(&json)?;
+        let from = format!(r#"{{"tokens":{tokens},"#);
+        let to = format!(r#"{{"tokens":{},"#, tokens + 1);
+        assert!(json.contains(&from));
+        let err =
+            serde_json::from_str::<GameState>(&json.replacen(&from, &to, 1))
+                .unwrap_err()
+                .to_string();
+        assert!(err.contains("but templates granted"), "{err}");
+        // Tools saved without their tokens have been paid for once.
+        let tool = |cost| Tool {
+            tool_type: ToolType::FlipDraftedDie,
+            cost,
+            tokens: 0,
+            used: false,
+        };
+        let game = GameState::builder(2).tools(vec![tool(1)]).build()?;
+        let json = serde_json::to_string(&game)?
+            .replace(r#""cost":1,"tokens":0,"#, r#""cost":2,"#);
+        let restored: GameState = serde_json::from_str(&json)?;
+        assert_eq!(restored.tools[0].tokens, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_actions_by_die_id() -> Result<(), DynError> {
+        let mut game =
+            GameState::builder(2).draft_pool(dice("R2 Y2 G3")).build()?;
+        // The index is ignored when the die is named by id.
+        let draft = TurnAction {
+            die_id: Some(2),
+            ..TurnAction::new(ActionType::DraftDie(0, None), Some((0, 0)))
+        };
+        game.take_turn(&draft)?;
+        let die = game.players[0].board[0][0].die.ok_or("no die placed")?;
+        assert_eq!((die.to_string(), die.id()), ("G3".into(), Some(2)));
+        assert_eq!(game.draft_pool.len(), 2);
+        let err = game.take_turn(&draft).unwrap_err().to_string();
+        assert!(err.contains("not in the draft pool"), "{err}");
+        // Tools can name round track dice by id too.
+        let mut game = GameState::builder(2)
+            .draft_pool(dice("R2 Y2"))
+            .round_track(vec![dice("G3 B4")])
+            .tools(vec![Tool {
+                tool_type: ToolType::SwapDraftedDieWithRoundTrack,
+                cost: 1,
+                tokens: 0,
+                used: false,
+            }])
+            .tokens(0, 1)
+            .build()?;
+        let swap = TurnAction {
+            die_id: Some(1),
+            ..TurnAction::use_tool(
+                0,
+                ToolData::SwapDraftedDieWithRoundTrack {
+                    draft_idx: 0,
+                    round_idx: (0, 0),
+                    round_die_id: Some(3),
+                },
+            )
+        };
+        game.take_turn(&swap)?;
+        let pool: Vec<_> =
+            game.draft_pool.iter().map(|d| d.to_string()).collect();
+        let track: Vec<_> =
+            game.round_track[0].iter().map(|d| d.to_string()).collect();
+        assert_eq!(pool, ["R2", "B4"]);
+        assert_eq!(track, ["G3", "Y2"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_solo_payment() -> Result<(), DynError> {
+        let mut game = GameState::builder(1)
+            .draft_pool(dice("G2 G3 R4"))
+            .tools(vec![Tool {
+                tool_type: ToolType::FlipDraftedDie,
+                cost: 1,
+                tokens: 0,
+                used: false,
+            }])
+            .build()?;
+        let flip = |draft_idx| TurnAction {
+            payment: Some(0),
+            ..TurnAction::use_tool(0, ToolData::FlipDraftedDie { draft_idx })
+        };
+        // A failed tool keeps the die it would have been paid with.
+        assert!(game.take_turn(&flip(3)).is_err());
+        let pool = |game: &GameState| {
+            game.draft_pool
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(pool(&game), ["G2", "G3", "R4"]);
+        game.take_turn(&flip(1))?;
+        assert_eq!(pool(&game), ["G4", "R4"]);
+        assert!(game.tools[0].used);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wildcard_must_be_placed() -> Result<(), DynError> {
+        let mut game = GameState::builder(2)
+            .seed(3)
+            .tokens(0, 3)
+            .draft_pool(dice("R2 Y2 G3"))
+            .tools(vec![Tool {
+                tool_type: ToolType::SwapDraftedDieWithBag,
+                cost: 1,
+                tokens: 0,
+                used: false,
+            }])
+            .build()?;
+        game.take_turn(&TurnAction::use_tool(
+            0,
+            ToolData::SwapDraftedDieWithBag { draft_idx: 1 },
+        ))?;
+        assert_eq!(game.pending_wild(), Some(1));
+        let json = serde_json::to_string(&game)?;
+        let restored: GameState = serde_json::from_str(&json)?;
+        assert!(restored.draft_pool[1].is_wild(), "{json}");
+        // Other dice can't be drafted, and an empty board can always take
+        // the wildcard, so it can't be passed or returned either.
+        for bad in [
+            TurnAction::pass(),
+            TurnAction::new(ActionType::DraftDie(0, None), Some((0, 0))),
+            TurnAction::new(ActionType::DraftDie(1, None), Some((0, 0))),
+            TurnAction::new(ActionType::DraftDie(1, Some(4)), None),
+        ] {
+            assert!(game.clone().take_turn(&bad).is_err(), "{bad:?}");
+        }
+        game.take_turn(&TurnAction::new(
+            ActionType::DraftDie(1, Some(4)),
+            Some((0, 0)),
+        ))?;
+        assert_eq!(game.players[0].board[0][0].die.map(|d| d.face), Some(4));
+        assert_eq!(game.pending_wild(), None);
+        Ok(())
+    }
+}
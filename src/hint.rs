@@ -0,0 +1,162 @@
+use serde::Serialize;
+
+use crate::agent::Agent;
+use crate::eval::{EvalWeights, evaluate, unfillable_cells};
+use crate::game::{GameState, Player};
+use crate::objective::Objective;
+use crate::tool::ToolType;
+use crate::turn::{ActionType, TurnAction};
+
+/// An action suggested to the current player, with what it's expected to
+/// do for their score.
+#[derive(Debug, Clone, Serialize)]
+pub struct Hint {
+    pub action: TurnAction,
+    // Change in the player's estimated final score.
+    pub score_delta: f64,
+    pub reasons: Vec<HintReason>,
+}
+
+/// Why an action was suggested, or what it costs.
+#[derive(Debug, Clone, Serialize)]
+pub enum HintReason {
+    // Points gained (or lost) on a public objective, e.g. by completing a
+    // row for `RowColors`.
+    Objective { objective: Objective, points: i32 },
+    // Pips added in the player's secret color.
+    SecretPips { pips: i32 },
+    UsesTool { tool: ToolType },
+    // Empty cells that can no longer be filled.
+    BlocksCells { cells: usize },
+    Pass,
+}
+
+// Up to `k` actions the agent suggests for the current player, best first.
+// Their scores are estimated with the agent's own weights.
+pub fn hints(game: &GameState, agent: &dyn Agent, k: usize) -> Vec<Hint> {
+    let idx = game.curr_player_idx;
+    let default = EvalWeights::default();
+    let weights = agent.weights().unwrap_or(&default);
+    let before = evaluate(game, idx, weights);
+    agent
+        .suggest_actions(game, k)
+        .into_iter()
+        .filter_map(|action| {
+            let mut next = game.clone();
+            next.take_turn(&action).ok()?;
+            Some(Hint {
+                score_delta: evaluate(&next, idx, weights) - before,
+                reasons: reasons(game, &next, &action),
+                action,
+            })
+        })
+        .collect()
+}
+
+fn reasons(
+    game: &GameState,
+    next: &GameState,
+    action: &TurnAction,
+) -> Vec<HintReason> {
+    let idx = game.curr_player_idx;
+    let (old, new) = (&game.players[idx], &next.players[idx]);
+    let mut reasons = Vec::new();
+    match action.idx {
+        ActionType::UseTool(tool) => reasons.push(HintReason::UsesTool {
+            tool: game.tools[tool].tool_type,
+        }),
+        ActionType::DraftDie(_, None) if action.coords.is_none() => {
+            reasons.push(HintReason::Pass);
+        }
+        _ => {}
+    }
+    for &objective in &game.objectives {
+        let points = objective.score(&new.board) - objective.score(&old.board);
+        if points != 0 {
+            reasons.push(HintReason::Objective { objective, points });
+        }
+    }
+    let pips = secret_pips(new) - secret_pips(old);
+    if pips != 0 {
+        reasons.push(HintReason::SecretPips { pips });
+    }
+    let cells = unfillable_cells(&new.board)
+        .saturating_sub(unfillable_cells(&old.board));
+    if cells > 0 {
+        reasons.push(HintReason::BlocksCells { cells });
+    }
+    reasons
+}
+
+// Sum of the player's dice in either of their secret colors.
+fn secret_pips(player: &Player) -> i32 {
+    player
+        .board
+        .iter()
+        .flatten()
+        .filter_map(|cell| cell.die)
+        .filter(|die| {
+            Some(die.color) == player.secret
+                || Some(die.color) == player.second_secret
+        })
+        .map(|die| die.face as i32)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::HeuristicAgent;
+    use crate::board::parse_board;
+    use crate::color::Color;
+    use crate::turn::TurnPhase;
+
+    #[test]
+    fn test_hint_reasons() -> Result<(), Box<dyn std::error::Error>> {
+        let board = parse_board(
+            "
+            R1 Y2 G3 B4 __
+            __ __ __ __ __
+            __ __ __ __ __
+            __ __ __ __ __
+            ",
+        )?;
+        let game = GameState::builder(2)
+            .board(0, board)
+            .secret(0, Color::Purple)
+            .draft_pool(vec!["P5".parse()?, "R6".parse()?])
+            .round_track(vec![vec!["Y1".parse()?], vec!["G1".parse()?]])
+            .objectives(vec![Objective::RowColors(6)])
+            .phase(TurnPhase::FirstDraft)
+            .build()?;
+        let hints = hints(&game, &HeuristicAgent::default(), 100);
+        let best = &hints[0];
+        assert_eq!(best.action.coords, Some((0, 4)));
+        assert!(best.score_delta > 0.0);
+        assert!(matches!(
+            best.reasons[..],
+            [
+                HintReason::Objective {
+                    objective: Objective::RowColors(6),
+                    points: 6
+                },
+                HintReason::SecretPips { pips: 5 }
+            ]
+        ));
+        let pass = hints.iter().find(|h| h.action.coords.is_none()).unwrap();
+        assert!(matches!(pass.reasons[..], [HintReason::Pass]));
+        // Scores are estimated with the agent's weights.
+        let weights = EvalWeights {
+            secret_color: 2.0,
+            ..Default::default()
+        };
+        let agent = HeuristicAgent::new(weights);
+        let weighted = super::hints(&game, &agent, 100);
+        let same = weighted
+            .iter()
+            .find(|h| h.action.coords == best.action.coords)
+            .unwrap();
+        assert!(same.score_delta > best.score_delta);
+        Ok(())
+    }
+}
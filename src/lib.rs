@@ -1,10 +1,16 @@
 pub mod agent;
 pub mod api;
-mod board;
+pub mod board;
 mod color;
+pub mod config;
 pub mod constants;
+pub mod engine;
+pub mod env;
+pub mod eval;
+pub mod event;
 pub mod game;
+pub mod hint;
 mod objective;
-mod template;
+pub mod template;
 mod tool;
 pub mod turn;
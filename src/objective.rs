@@ -31,6 +31,21 @@ pub const ALL_OBJECTIVES: [Objective; 10] = [
 ];
 
 impl Objective {
+    // Points awarded for each completed pattern.
+    pub fn points(self) -> i32 {
+        match self {
+            Objective::ColumnNumbers(n)
+            | Objective::RowNumbers(n)
+            | Objective::Numbers(n)
+            | Objective::ColumnColors(n)
+            | Objective::RowColors(n)
+            | Objective::Colors(n)
+            | Objective::Pair12(n)
+            | Objective::Pair34(n)
+            | Objective::Pair56(n)
+            | Objective::ColorDiagonals(n) => n,
+        }
+    }
     pub fn score(self, board: &[[BoardCell; BOARD_COLS]; BOARD_ROWS]) -> i32 {
         match self {
             Objective::ColumnNumbers(n) => {
@@ -159,78 +174,59 @@ fn has_diag(row: &[BoardCell], j: usize, color: Color) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::board::parse_board;
 
     #[test]
     fn test_has_diag() {
-        let empty = BoardCell::default();
-        let red = BoardCell::with_die(Dice {
-            color: Color::Red,
-            face: 3,
-        });
-        let blue = BoardCell::with_die(Dice {
-            color: Color::Blue,
-            face: 4,
-        });
-        let row = [empty.clone(), red, blue, empty.clone(), empty];
-
-        assert!(has_diag(&row, 0, Color::Red), "red @ 0 has diag");
-        assert!(!has_diag(&row, 0, Color::Blue), "blue @ 0 no diag");
-        assert!(!has_diag(&row, 1, Color::Red), "red @ 1 no diag");
-        assert!(has_diag(&row, 1, Color::Blue), "blue @ 1 has diag");
-        assert!(has_diag(&row, 2, Color::Red), "red @ 2 has diag");
-        assert!(!has_diag(&row, 2, Color::Blue), "blue @ 2 no diag");
-        assert!(!has_diag(&row, 3, Color::Red), "red @ 3 no diag");
-        assert!(has_diag(&row, 3, Color::Blue), "blue @ 3 has diag");
+        let board = parse_board(
+            "
+            __ R3 B4 __ __
+            __ __ __ __ __
+            __ __ __ __ __
+            __ __ __ __ __
+            ",
+        )
+        .unwrap();
+        let row = &board[0];
+        assert!(has_diag(row, 0, Color::Red), "red @ 0 has diag");
+        assert!(!has_diag(row, 0, Color::Blue), "blue @ 0 no diag");
+        assert!(!has_diag(row, 1, Color::Red), "red @ 1 no diag");
+        assert!(has_diag(row, 1, Color::Blue), "blue @ 1 has diag");
+        assert!(has_diag(row, 2, Color::Red), "red @ 2 has diag");
+        assert!(!has_diag(row, 2, Color::Blue), "blue @ 2 no diag");
+        assert!(!has_diag(row, 3, Color::Red), "red @ 3 no diag");
+        assert!(has_diag(row, 3, Color::Blue), "blue @ 3 has diag");
     }
 
     #[test]
     fn test_color_diagonals() {
-        let empty = BoardCell::default();
-        let red = BoardCell::with_die(Dice {
-            color: Color::Red,
-            face: 3,
-        });
-        let blue = BoardCell::with_die(Dice {
-            color: Color::Blue,
-            face: 4,
-        });
-        // Board layout:
-        // . R . . .
-        // R . B . .
-        // . B . R .
-        // . . R . B
-        let board = [
-            [
-                empty.clone(),
-                red.clone(),
-                empty.clone(),
-                empty.clone(),
-                empty.clone(),
-            ],
-            [
-                red.clone(),
-                empty.clone(),
-                blue.clone(),
-                empty.clone(),
-                empty.clone(),
-            ],
-            [
-                empty.clone(),
-                blue.clone(),
-                empty.clone(),
-                red.clone(),
-                empty.clone(),
-            ],
-            [
-                empty.clone(),
-                empty.clone(),
-                red.clone(),
-                empty.clone(),
-                blue.clone(),
-            ],
-        ];
-
+        let board = parse_board(
+            "
+            __ R3 __ __ __
+            R3 __ B4 __ __
+            __ B4 __ R3 __
+            __ __ R3 __ B4
+            ",
+        )
+        .unwrap();
         assert_eq!(color_diagonals(&board), 6);
         assert_eq!(Objective::ColorDiagonals(2).score(&board), 12);
     }
+
+    #[test]
+    fn test_row_and_column_objectives() {
+        let board = parse_board(
+            "
+            R1 Y2 G3 B4 P5
+            Y3 G4 B5 P6 R1
+            __ _2 __ __ __
+            B6 P1 R2 Y3 G4
+            ",
+        )
+        .unwrap();
+        assert_eq!(Objective::RowColors(6).score(&board), 18);
+        assert_eq!(Objective::RowNumbers(5).score(&board), 15);
+        assert_eq!(Objective::ColumnColors(5).score(&board), 0);
+        assert_eq!(Objective::Pair12(2).score(&board), 4);
+    }
 }
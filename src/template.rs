@@ -1,15 +1,58 @@
-use crate::color::Color;
+use crate::board::{parse_grid, split_headers};
+use crate::color::{Color, Dice};
 use crate::constants::{BOARD_COLS, BOARD_ROWS};
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::sync::LazyLock;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+type DynError = Box<dyn std::error::Error>;
+
+#[derive(Debug, Clone, Copy, Serialize)]
 #[serde(untagged)]
 pub enum Slot {
     Any,
     Color(Color),
     Face(u8),
 }
+impl Slot {
+    // Whether a die satisfies this slot's color or face restriction.
+    pub fn accepts(self, die: Dice) -> bool {
+        match self {
+            Slot::Color(color) => color == die.color,
+            Slot::Face(face) => face == die.face,
+            Slot::Any => true,
+        }
+    }
+}
+// Slots are serialized as `null`, a color like `"R"`, or a face from 1 to 6.
+impl<'de> Deserialize<'de> for Slot {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawSlot {
+            Any(()),
+            Color(String),
+            Face(i64),
+        }
+        let invalid = |msg: String| serde::de::Error::custom(msg);
+        match RawSlot::deserialize(deserializer)? {
+            RawSlot::Any(()) => Ok(Slot::Any),
+            RawSlot::Color(s) => s
+                .parse()
+                .map(Slot::Color)
+                .map_err(|_| invalid(format!("Invalid slot color {s:?}"))),
+            RawSlot::Face(face @ 1..=6) => Ok(Slot::Face(face as u8)),
+            RawSlot::Face(face) => Err(invalid(format!(
+                "Invalid slot face {face}: must be 1 to 6"
+            ))),
+        }
+    }
+}
 impl Display for Slot {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -19,289 +62,249 @@ impl Display for Slot {
         }
     }
 }
+impl FromStr for Slot {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.as_bytes() {
+            [b'_', b'_'] => Ok(Slot::Any),
+            [b'_', face @ b'1'..=b'6'] => Ok(Slot::Face(face - b'0')),
+            [_, b'_'] => s[..1]
+                .parse()
+                .map(Slot::Color)
+                .map_err(|_| format!("Invalid slot: {s}")),
+            _ => Err(format!("Invalid slot: {s}")),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RawTemplate")]
 pub struct BoardTemplate {
     pub slots: [[Slot; BOARD_COLS]; BOARD_ROWS],
     pub value: u8,
+    #[serde(flatten)]
+    pub info: TemplateInfo,
+}
+
+#[derive(Deserialize)]
+struct RawTemplate {
+    slots: [[Slot; BOARD_COLS]; BOARD_ROWS],
+    value: u8,
+    #[serde(flatten)]
+    info: TemplateInfo,
+}
+impl TryFrom<RawTemplate> for BoardTemplate {
+    type Error = String;
+    fn try_from(raw: RawTemplate) -> Result<Self, Self::Error> {
+        if !VALUE_RANGE.contains(&raw.value) {
+            return Err(format!(
+                "Invalid template value {}: must be {} to {}",
+                raw.value,
+                VALUE_RANGE.start(),
+                VALUE_RANGE.end()
+            ));
+        }
+        Ok(Self {
+            slots: raw.slots,
+            value: raw.value,
+            info: raw.info,
+        })
+    }
+}
+
+/// Identifies a template, so the chosen window can be shown to players.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemplateInfo {
+    pub name: String,
+    // Index of the card in the deck it was dealt from; both sides share it.
+    pub card: usize,
+    pub difficulty: u8,
+    // Tokens the template granted the player who selected it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<u8>,
+}
+
+impl FromStr for BoardTemplate {
+    type Err = String;
+    // Parses one template in the format of `data/templates.txt`: `name:`,
+    // `value:` and optional `difficulty:` headers, then the grid printed by
+    // `Player::pretty_print` for an empty board.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (headers, grid) = split_headers(s);
+        let mut info = TemplateInfo::default();
+        let mut value = None;
+        let mut difficulty = None;
+        for (key, val) in headers {
+            let number = || -> Result<u8, String> {
+                val.parse().map_err(|e| format!("Invalid {key}: {e}"))
+            };
+            match key {
+                "name" => info.name = val.to_string(),
+                "value" => value = Some(number()?),
+                "difficulty" => difficulty = Some(number()?),
+                _ => return Err(format!("Unknown header: {key}")),
+            }
+        }
+        let value = value.ok_or("Expected a `value:` header")?;
+        if !VALUE_RANGE.contains(&value) {
+            return Err(format!(
+                "Value must be between {} and {}, got {value}",
+                VALUE_RANGE.start(),
+                VALUE_RANGE.end()
+            ));
+        }
+        // Official templates grant one token per point of difficulty.
+        info.difficulty = difficulty.unwrap_or(value);
+        Ok(Self {
+            slots: parse_grid(grid)?,
+            value,
+            info,
+        })
+    }
+}
+
+// Allowed number of tokens granted by a template.
+const VALUE_RANGE: RangeInclusive<u8> = 3..=6;
+
+const BUILTIN_TEMPLATES: &str = include_str!("../data/templates.txt");
+static BUILTIN_CARDS: LazyLock<Vec<[BoardTemplate; 2]>> = LazyLock::new(|| {
+    parse_template_cards(BUILTIN_TEMPLATES)
+        .expect("Built-in templates should be valid")
+});
+
+// The template cards embedded from `data/templates.txt`.
+pub fn builtin_template_cards() -> &'static [[BoardTemplate; 2]] {
+    &BUILTIN_CARDS
+}
+
+// Parses template cards in the format of `data/templates.txt`. Card ids
+// are numbered from zero in the order they appear.
+pub fn parse_template_cards(
+    text: &str,
+) -> Result<Vec<[BoardTemplate; 2]>, DynError> {
+    let err = |line: usize, msg: String| -> DynError {
+        format!("Invalid template on line {line}: {msg}").into()
+    };
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .peekable();
+    let mut cards = Vec::new();
+    let mut card = Vec::new();
+    let mut last_line = 0;
+    let finish_card = |cards: &mut Vec<[BoardTemplate; 2]>,
+                       card: &mut Vec<BoardTemplate>,
+                       line: usize| {
+        let sides = std::mem::take(card).try_into().map_err(|_| {
+            err(line, "each card must have two templates".into())
+        })?;
+        cards.push(sides);
+        Ok::<(), DynError>(())
+    };
+    while let Some(&(num, line)) = lines.peek() {
+        last_line = num;
+        if line == "---" {
+            lines.next();
+            finish_card(&mut cards, &mut card, num)?;
+            continue;
+        }
+        // Each template is its header lines, e.g. `name: Bellesguard`,
+        // followed by its rows of slots.
+        let mut text = String::new();
+        while let Some((num, line)) =
+            lines.next_if(|(_, line)| line.contains(':'))
+        {
+            last_line = num;
+            text += line;
+            text.push('\n');
+        }
+        let mut rows = 0;
+        while rows < BOARD_ROWS
+            && let Some((num, line)) =
+                lines.next_if(|(_, line)| *line != "---" && !line.contains(':'))
+        {
+            last_line = num;
+            text += line;
+            text.push('\n');
+            rows += 1;
+        }
+        let mut template: BoardTemplate =
+            text.parse().map_err(|e| err(num, e))?;
+        if template.info.name.is_empty() {
+            return Err(err(num, "expected a `name:` header".into()));
+        }
+        template.info.card = cards.len();
+        card.push(template);
+    }
+    finish_card(&mut cards, &mut card, last_line)?;
+    Ok(cards)
 }
 
-// TODO: move this to a data file.
-const RED: Slot = Slot::Color(Color::Red);
-const YEL: Slot = Slot::Color(Color::Yellow);
-const GRN: Slot = Slot::Color(Color::Green);
-const BLU: Slot = Slot::Color(Color::Blue);
-const PPL: Slot = Slot::Color(Color::Purple);
-const DF1: Slot = Slot::Face(1);
-const DF2: Slot = Slot::Face(2);
-const DF3: Slot = Slot::Face(3);
-const DF4: Slot = Slot::Face(4);
-const DF5: Slot = Slot::Face(5);
-const DF6: Slot = Slot::Face(6);
-const ANY: Slot = Slot::Any;
-pub const ALL_BOARD_TEMPLATES: [[BoardTemplate; 2]; 12] = [
-    [
-        BoardTemplate {
-            // Bellesguard
-            slots: [
-                [BLU, DF6, ANY, ANY, YEL],
-                [ANY, DF3, BLU, ANY, ANY],
-                [ANY, DF5, DF6, DF2, ANY],
-                [ANY, DF4, ANY, DF1, GRN],
-            ],
-            value: 3,
-        },
-        BoardTemplate {
-            // Batllo
-            slots: [
-                [ANY, ANY, DF6, ANY, ANY],
-                [ANY, DF5, BLU, DF4, ANY],
-                [DF3, GRN, YEL, PPL, DF2],
-                [DF1, DF4, RED, DF5, DF3],
-            ],
-            value: 5,
-        },
-    ],
-    [
-        BoardTemplate {
-            // Fractal Drops
-            slots: [
-                [ANY, DF4, ANY, YEL, DF6],
-                [RED, ANY, DF2, ANY, ANY],
-                [ANY, ANY, RED, PPL, DF1],
-                [BLU, YEL, ANY, ANY, ANY],
-            ],
-            value: 3,
-        },
-        BoardTemplate {
-            // Ripples of Light
-            slots: [
-                [ANY, ANY, ANY, RED, DF5],
-                [ANY, ANY, PPL, DF4, BLU],
-                [ANY, BLU, DF3, YEL, DF6],
-                [YEL, DF2, GRN, DF1, RED],
-            ],
-            value: 5,
-        },
-    ],
-    [
-        BoardTemplate {
-            // Luz Celestial
-            slots: [
-                [ANY, ANY, RED, DF5, ANY],
-                [PPL, DF4, ANY, GRN, DF3],
-                [DF6, ANY, ANY, BLU, ANY],
-                [ANY, YEL, DF2, ANY, ANY],
-            ],
-            value: 3,
-        },
-        BoardTemplate {
-            // Fulgor del Cielo
-            slots: [
-                [ANY, BLU, RED, ANY, ANY],
-                [ANY, DF4, DF5, ANY, BLU],
-                [BLU, DF2, ANY, RED, DF5],
-                [DF6, RED, DF3, DF1, ANY],
-            ],
-            value: 5,
-        },
-    ],
-    [
-        BoardTemplate {
-            // Sun Catcher
-            slots: [
-                [ANY, BLU, DF2, ANY, YEL],
-                [ANY, DF4, ANY, RED, ANY],
-                [ANY, ANY, DF5, YEL, ANY],
-                [GRN, DF3, ANY, ANY, PPL],
-            ],
-            value: 3,
-        },
-        BoardTemplate {
-            // Shadow Thief
-            slots: [
-                [DF6, PPL, ANY, ANY, DF5],
-                [DF5, ANY, PPL, ANY, ANY],
-                [RED, DF6, ANY, PPL, ANY],
-                [YEL, RED, DF5, DF4, DF3],
-            ],
-            value: 5,
-        },
-    ],
-    [
-        BoardTemplate {
-            // Symphony of Light
-            slots: [
-                [DF2, ANY, DF5, ANY, DF1],
-                [YEL, DF6, PPL, DF2, RED],
-                [ANY, BLU, DF4, GRN, ANY],
-                [ANY, DF3, ANY, DF5, ANY],
-            ],
-            value: 6,
-        },
-        BoardTemplate {
-            // Virtus
-            slots: [
-                [DF4, ANY, DF2, DF5, GRN],
-                [ANY, ANY, DF6, GRN, DF2],
-                [ANY, DF3, GRN, DF4, ANY],
-                [DF5, GRN, DF1, ANY, ANY],
-            ],
-            value: 5,
-        },
-    ],
-    [
-        BoardTemplate {
-            // Aurorae Magnificus
-            slots: [
-                [DF5, GRN, BLU, PPL, DF2],
-                [PPL, ANY, ANY, ANY, YEL],
-                [YEL, ANY, DF6, ANY, PPL],
-                [DF1, ANY, ANY, GRN, DF4],
-            ],
-            value: 5,
-        },
-        BoardTemplate {
-            // Aurora Sagradis
-            slots: [
-                [RED, ANY, BLU, ANY, YEL],
-                [DF4, PPL, DF3, GRN, DF2],
-                [ANY, DF1, ANY, DF5, ANY],
-                [ANY, ANY, DF6, ANY, ANY],
-            ],
-            value: 4,
-        },
-    ],
-    [
-        BoardTemplate {
-            // Industria
-            slots: [
-                [DF1, RED, DF3, ANY, DF6],
-                [DF5, DF4, RED, DF2, ANY],
-                [ANY, ANY, DF5, RED, DF1],
-                [ANY, ANY, ANY, DF3, RED],
-            ],
-            value: 5,
-        },
-        BoardTemplate {
-            // Via Lux
-            slots: [
-                [YEL, ANY, DF6, ANY, ANY],
-                [ANY, DF1, DF5, ANY, DF2],
-                [DF3, YEL, RED, PPL, ANY],
-                [ANY, ANY, DF4, DF3, RED],
-            ],
-            value: 4,
-        },
-    ],
-    [
-        BoardTemplate {
-            // Sun's Glory
-            slots: [
-                [DF1, PPL, YEL, ANY, DF4],
-                [PPL, YEL, ANY, ANY, DF6],
-                [YEL, ANY, ANY, DF5, DF3],
-                [ANY, DF5, DF4, DF2, DF1],
-            ],
-            value: 6,
-        },
-        BoardTemplate {
-            // Firelight
-            slots: [
-                [DF3, DF4, DF1, DF5, ANY],
-                [ANY, DF6, DF2, ANY, YEL],
-                [ANY, ANY, ANY, YEL, RED],
-                [DF5, ANY, YEL, RED, DF6],
-            ],
-            value: 5,
-        },
-    ],
-    [
-        BoardTemplate {
-            // Lux Mundi
-            slots: [
-                [ANY, ANY, DF1, ANY, ANY],
-                [DF1, GRN, DF3, BLU, DF2],
-                [BLU, DF5, DF4, DF6, GRN],
-                [ANY, BLU, DF5, GRN, ANY],
-            ],
-            value: 6,
-        },
-        BoardTemplate {
-            // Lux Astram
-            slots: [
-                [ANY, DF1, GRN, PPL, DF4],
-                [DF6, PPL, DF2, DF5, GRN],
-                [DF1, GRN, DF5, DF3, PPL],
-                [ANY, ANY, ANY, ANY, ANY],
-            ],
-            value: 5,
-        },
-    ],
-    [
-        BoardTemplate {
-            // Water of Life
-            slots: [
-                [DF6, BLU, ANY, ANY, DF1],
-                [ANY, DF5, BLU, ANY, ANY],
-                [DF4, RED, DF2, BLU, ANY],
-                [GRN, DF6, YEL, DF3, PPL],
-            ],
-            value: 6,
-        },
-        BoardTemplate {
-            // Gravitas
-            slots: [
-                [DF1, ANY, DF3, BLU, ANY],
-                [ANY, DF2, BLU, ANY, ANY],
-                [DF6, BLU, ANY, DF4, ANY],
-                [BLU, DF5, DF2, ANY, DF1],
-            ],
-            value: 5,
-        },
-    ],
-    [
-        BoardTemplate {
-            // Firmitas
-            slots: [
-                [PPL, DF6, ANY, ANY, DF3],
-                [DF5, PPL, DF3, ANY, ANY],
-                [ANY, DF2, PPL, DF1, ANY],
-                [ANY, DF1, DF5, PPL, DF4],
-            ],
-            value: 5,
-        },
-        BoardTemplate {
-            // Kaleidoscopic Dream
-            slots: [
-                [YEL, BLU, ANY, ANY, DF1],
-                [GRN, ANY, DF5, ANY, DF4],
-                [DF3, ANY, RED, ANY, GRN],
-                [DF2, ANY, ANY, BLU, YEL],
-            ],
-            value: 4,
-        },
-    ],
-    [
-        BoardTemplate {
-            // Chromatic Splendor
-            slots: [
-                [ANY, ANY, GRN, ANY, ANY],
-                [DF2, YEL, DF5, BLU, DF1],
-                [ANY, RED, DF3, PPL, ANY],
-                [DF1, ANY, DF6, ANY, DF4],
-            ],
-            value: 4,
-        },
-        BoardTemplate {
-            // Comitas
-            slots: [
-                [YEL, ANY, DF2, ANY, DF6],
-                [ANY, DF4, ANY, DF5, YEL],
-                [ANY, ANY, ANY, YEL, DF5],
-                [DF1, DF2, YEL, DF3, ANY],
-            ],
-            value: 5,
-        },
-    ],
-];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::MAX_PLAYERS;
+
+    #[test]
+    fn test_builtin_templates() {
+        let cards = parse_template_cards(BUILTIN_TEMPLATES).unwrap();
+        // Every player is dealt two cards.
+        assert!(cards.len() >= 2 * MAX_PLAYERS);
+        assert_eq!(cards[0][0].value, 3);
+        assert_eq!(cards[0][0].info.name, "Bellesguard");
+        assert_eq!(cards[0][1].info.name, "Batllo");
+        assert_eq!(cards[11][1].info.card, 11);
+        assert!(cards.iter().flatten().all(|t| t.info.difficulty == t.value));
+        // Deserialized templates are checked too.
+        let mut json = serde_json::to_value(&cards[0][0]).unwrap();
+        assert_eq!(json["slots"][0][0], "B");
+        json["value"] = 9.into();
+        let err = serde_json::from_value::<BoardTemplate>(json).unwrap_err();
+        assert!(err.to_string().contains("value 9"), "{err}");
+        assert_eq!(cards[0][0].slots[0][0].to_string(), "B_");
+        assert_eq!(cards[0][0].slots[0][1].to_string(), "_6");
+        assert_eq!(cards[0][0].slots[0][2].to_string(), "__");
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        let rows = "__ __ __ __ __\n".repeat(BOARD_ROWS);
+        let card = format!(
+            "name: A\nvalue: 4\n{rows}\nname: B\nvalue: 5\ndifficulty: 2\n{rows}"
+        );
+        let cards = parse_template_cards(&card).unwrap();
+        assert_eq!(cards[0][1].info.name, "B");
+        assert_eq!(cards[0][1].info.difficulty, 2);
+        let check =
+            |text: &str| parse_template_cards(text).unwrap_err().to_string();
+        assert!(
+            check(&format!("name: A\nvalue: 4\n{rows}"))
+                .contains("two templates")
+        );
+        assert!(
+            check(&card.replace("value: 5", "value: 9"))
+                .contains("between 3 and 6")
+        );
+        assert!(
+            check(&card.replacen("__ __ __ __ __", "__ __ __ __", 1))
+                .contains("5 cells per row")
+        );
+        assert!(
+            check(&card.replacen("__ __", "X_ __", 1))
+                .contains("Invalid slot: X_")
+        );
+        assert!(
+            check(&card.replace("difficulty: 2\n__", "difficulty: 2\n_7"))
+                .contains("line 8")
+        );
+        assert!(check("name: A\nvalue: 4\n__ __ __ __ __").contains("4 rows"));
+        assert!(check("name: A\n").contains("`value:` header"));
+        assert!(
+            check(&card.replace("name: B", "nmae: B"))
+                .contains("Unknown header")
+        );
+    }
+}
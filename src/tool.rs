@@ -1,12 +1,19 @@
+use crate::color::{Color, DieId};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tool {
     pub tool_type: ToolType,
     pub cost: u8,
+    // Tokens players have placed on this tool to use it.
+    #[serde(default)]
+    pub tokens: u8,
+    // Solo games only: each tool can be used once.
+    #[serde(default)]
+    pub used: bool,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum ToolType {
     // Tools that modify the draft pool.
     BumpDraftedDie, // +/- 1
@@ -24,6 +31,25 @@ pub enum ToolType {
     DraftTwoDice, // only before first draft, skips second draft
     PlaceIgnoringAdjacency,
 }
+impl ToolType {
+    // Color of the die that must be spent to use this tool in a solo game.
+    pub fn color(self) -> Color {
+        match self {
+            ToolType::BumpDraftedDie => Color::Purple,
+            ToolType::MoveDieIgnoringColor => Color::Blue,
+            ToolType::MoveDieIgnoringValue => Color::Red,
+            ToolType::MoveExactlyTwoDice => Color::Yellow,
+            ToolType::SwapDraftedDieWithRoundTrack => Color::Green,
+            ToolType::RerollDraftedDie => Color::Purple,
+            ToolType::RerollAllDiceInPool => Color::Blue,
+            ToolType::DraftTwoDice => Color::Red,
+            ToolType::PlaceIgnoringAdjacency => Color::Yellow,
+            ToolType::FlipDraftedDie => Color::Green,
+            ToolType::SwapDraftedDieWithBag => Color::Purple,
+            ToolType::MoveUpToTwoDiceMatchingColor => Color::Blue,
+        }
+    }
+}
 // TODO: Uncomment tools as they are implemented.
 pub const ALL_TOOL_TYPES: [ToolType; 7] = [
     ToolType::BumpDraftedDie,
@@ -55,6 +81,9 @@ pub enum ToolData {
     SwapDraftedDieWithRoundTrack {
         draft_idx: usize,
         round_idx: (usize, usize),
+        // The round track die by id. Overrides `round_idx`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        round_die_id: Option<DieId>,
     },
     SwapDraftedDieWithBag {
         draft_idx: usize,
@@ -79,42 +108,50 @@ pub enum ToolData {
     PlaceIgnoringAdjacency,
 }
 impl ToolData {
+    // Index of the draft pool die this tool acts on, if any.
+    pub fn draft_idx(&self) -> Option<usize> {
+        match *self {
+            Self::BumpDraftedDie { draft_idx, .. }
+            | Self::FlipDraftedDie { draft_idx }
+            | Self::RerollDraftedDie { draft_idx }
+            | Self::SwapDraftedDieWithRoundTrack { draft_idx, .. }
+            | Self::SwapDraftedDieWithBag { draft_idx } => Some(draft_idx),
+            _ => None,
+        }
+    }
+    pub fn draft_idx_mut(&mut self) -> Option<&mut usize> {
+        match self {
+            Self::BumpDraftedDie { draft_idx, .. }
+            | Self::FlipDraftedDie { draft_idx }
+            | Self::RerollDraftedDie { draft_idx }
+            | Self::SwapDraftedDieWithRoundTrack { draft_idx, .. }
+            | Self::SwapDraftedDieWithBag { draft_idx } => Some(draft_idx),
+            _ => None,
+        }
+    }
+    pub fn tool_type(&self) -> ToolType {
+        match self {
+            Self::BumpDraftedDie { .. } => ToolType::BumpDraftedDie,
+            Self::FlipDraftedDie { .. } => ToolType::FlipDraftedDie,
+            Self::RerollDraftedDie { .. } => ToolType::RerollDraftedDie,
+            Self::SwapDraftedDieWithRoundTrack { .. } => {
+                ToolType::SwapDraftedDieWithRoundTrack
+            }
+            Self::SwapDraftedDieWithBag { .. } => {
+                ToolType::SwapDraftedDieWithBag
+            }
+            Self::RerollAllDiceInPool => ToolType::RerollAllDiceInPool,
+            Self::MoveDieIgnoringColor { .. } => ToolType::MoveDieIgnoringColor,
+            Self::MoveDieIgnoringValue { .. } => ToolType::MoveDieIgnoringValue,
+            Self::MoveExactlyTwoDice { .. } => ToolType::MoveExactlyTwoDice,
+            Self::MoveUpToTwoDiceMatchingColor { .. } => {
+                ToolType::MoveUpToTwoDiceMatchingColor
+            }
+            Self::DraftTwoDice => ToolType::DraftTwoDice,
+            Self::PlaceIgnoringAdjacency => ToolType::PlaceIgnoringAdjacency,
+        }
+    }
     pub fn matches_type(&self, tool_type: ToolType) -> bool {
-        matches!(
-            (self, tool_type),
-            (Self::BumpDraftedDie { .. }, ToolType::BumpDraftedDie)
-                | (Self::FlipDraftedDie { .. }, ToolType::FlipDraftedDie)
-                | (Self::RerollDraftedDie { .. }, ToolType::RerollDraftedDie)
-                | (
-                    Self::SwapDraftedDieWithRoundTrack { .. },
-                    ToolType::SwapDraftedDieWithRoundTrack
-                )
-                | (
-                    Self::SwapDraftedDieWithBag { .. },
-                    ToolType::SwapDraftedDieWithBag
-                )
-                | (Self::RerollAllDiceInPool, ToolType::RerollAllDiceInPool)
-                | (
-                    Self::MoveDieIgnoringColor { .. },
-                    ToolType::MoveDieIgnoringColor
-                )
-                | (
-                    Self::MoveDieIgnoringValue { .. },
-                    ToolType::MoveDieIgnoringValue
-                )
-                | (
-                    Self::MoveExactlyTwoDice { .. },
-                    ToolType::MoveExactlyTwoDice
-                )
-                | (
-                    Self::MoveUpToTwoDiceMatchingColor { .. },
-                    ToolType::MoveUpToTwoDiceMatchingColor
-                )
-                | (Self::DraftTwoDice, ToolType::DraftTwoDice)
-                | (
-                    Self::PlaceIgnoringAdjacency,
-                    ToolType::PlaceIgnoringAdjacency
-                )
-        )
+        self.tool_type() == tool_type
     }
 }
@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+use crate::color::DieId;
 use crate::tool::{Tool, ToolData, ToolType};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum TurnPhase {
     SelectTemplate,
     FirstDraft,
@@ -15,15 +16,39 @@ pub struct TurnAction {
     pub idx: ActionType,
     pub coords: Option<(usize, usize)>,
     pub tool: Option<ToolData>,
+    // Solo games only: index of the draft pool die spent to use a tool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment: Option<usize>,
+    // Pool die to draft or use the tool on, by id. Overrides the pool index
+    // in `idx` or `tool`, which can shift as other dice are drafted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub die_id: Option<DieId>,
+    // Solo games only: the die spent to use a tool, by id. Overrides
+    // `payment`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_id: Option<DieId>,
 }
 impl TurnAction {
-    pub fn pass() -> Self {
+    // An action without a tool, payment or die ids.
+    pub fn new(idx: ActionType, coords: Option<(usize, usize)>) -> Self {
         Self {
-            idx: ActionType::DraftDie(0, None),
-            coords: None,
+            idx,
+            coords,
             tool: None,
+            payment: None,
+            die_id: None,
+            payment_id: None,
+        }
+    }
+    pub fn use_tool(idx: usize, data: ToolData) -> Self {
+        Self {
+            tool: Some(data),
+            ..Self::new(ActionType::UseTool(idx), None)
         }
     }
+    pub fn pass() -> Self {
+        Self::new(ActionType::DraftDie(0, None), None)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]